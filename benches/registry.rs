@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::Runtime;
+use truba::{Context, Message, MpscChannel};
+
+const ACTORS: u32 = 1000;
+const LOOKUPS_PER_TASK: u32 = 1000;
+
+struct Value;
+
+impl Message for Value {
+    type Channel = MpscChannel<Self>;
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the runtime")
+}
+
+fn run_concurrently(
+    runtime: &Runtime,
+    tasks: u32,
+    iters: u64,
+    lookup: impl Fn(u32) + Clone + Send + 'static,
+) -> Duration {
+    runtime.block_on(async move {
+        let started_at = Instant::now();
+        let handles: Vec<_> = (0..tasks)
+            .map(|task| {
+                let lookup = lookup.clone();
+                tokio::spawn(async move {
+                    for _ in 0..iters {
+                        for i in 0..LOOKUPS_PER_TASK {
+                            lookup(task.wrapping_mul(31).wrapping_add(i));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.expect("lookup task failed");
+        }
+        started_at.elapsed()
+    })
+}
+
+fn lookups(c: &mut Criterion) {
+    let runtime = runtime();
+    let ctx = Context::<u32>::new();
+    let _receivers: Vec<_> = (0..ACTORS).map(|id| ctx.actor_receiver::<Value>(id)).collect();
+    let _receiver = ctx.receiver::<Value>();
+
+    let mut group = c.benchmark_group("registry");
+    for tasks in [1, 16, 256] {
+        group.throughput(Throughput::Elements(u64::from(tasks * LOOKUPS_PER_TASK)));
+
+        let actor_ctx = ctx.clone();
+        group.bench_with_input(BenchmarkId::new("actor_sender", tasks), &tasks, |b, &tasks| {
+            b.iter_custom(|iters| {
+                let ctx = actor_ctx.clone();
+                run_concurrently(&runtime, tasks, iters, move |i| {
+                    ctx.actor_sender::<Value>(i % ACTORS);
+                })
+            })
+        });
+
+        let global_ctx = ctx.clone();
+        group.bench_with_input(BenchmarkId::new("sender", tasks), &tasks, |b, &tasks| {
+            b.iter_custom(|iters| {
+                let ctx = global_ctx.clone();
+                run_concurrently(&runtime, tasks, iters, move |_| {
+                    ctx.sender::<Value>();
+                })
+            })
+        });
+
+        let closed_ctx = ctx.clone();
+        group.bench_with_input(
+            BenchmarkId::new("is_actor_channel_closed", tasks),
+            &tasks,
+            |b, &tasks| {
+                b.iter_custom(|iters| {
+                    let ctx = closed_ctx.clone();
+                    run_concurrently(&runtime, tasks, iters, move |i| {
+                        ctx.is_actor_channel_closed::<Value>(i % ACTORS);
+                    })
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookups);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+use std::ops::ControlFlow;
+
+use truba::{Actor, Context, Message, MpscChannel, Receiver};
+
+struct Value(u32);
+
+impl Message for Value {
+    type Channel = MpscChannel<Self>;
+}
+
+struct MyActor {
+    id: String,
+    value: u32,
+}
+
+impl Actor for MyActor {
+    type Id = String;
+    type Inbox = Receiver<Value>;
+    type Message = Value;
+
+    fn inbox(ctx: &Context, id: &String) -> Self::Inbox {
+        ctx.actor_receiver::<Value>(id.clone())
+    }
+
+    async fn receive(value_in: &mut Self::Inbox) -> Option<Value> {
+        value_in.recv().await
+    }
+
+    async fn handle(&mut self, Value(value): Value) -> ControlFlow<()> {
+        self.value = value;
+        println!("{} receive value {value}", self.id);
+        ControlFlow::Continue(())
+    }
+
+    fn started(&mut self, _ctx: &Context) {
+        println!("run the {}", self.id);
+    }
+
+    fn stopped(self) {
+        println!("stop the {} with value {}", self.id, self.value);
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let ctx = Context::new();
+
+    for id in ["actor 1", "actor 2"] {
+        ctx.start_actor(
+            id,
+            MyActor {
+                id: id.into(),
+                value: 0,
+            },
+        );
+    }
+
+    ctx.actor_sender::<Value>("actor 1").send(Value(11)).await.ok();
+    ctx.actor_sender::<Value>("actor 2").send(Value(21)).await.ok();
+
+    ctx.shutdown().await;
+}
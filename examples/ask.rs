@@ -0,0 +1,36 @@
+use truba::{Context, Message, Request, RequestChannel};
+
+struct Sum(Vec<u32>);
+
+impl Message for Sum {
+    type Channel = RequestChannel<Self>;
+}
+
+impl Request for Sum {
+    type Response = u32;
+}
+
+struct Calculator;
+
+impl Calculator {
+    fn run(ctx: Context, actor_id: &'static str) {
+        let mut sum_in = ctx.actor_receiver::<Sum>(actor_id);
+
+        truba::spawn_event_loop!(ctx, {
+            Some((Sum(values), responder)) = sum_in.recv() => {
+                responder.respond(values.into_iter().sum()).ok();
+            },
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let ctx = Context::new();
+    Calculator::run(ctx.clone(), "calculator");
+
+    let sum = ctx.ask::<Sum>("calculator", Sum(vec![1, 2, 3])).await;
+    println!("sum = {sum:?}");
+
+    ctx.shutdown().await;
+}
@@ -13,16 +13,14 @@ struct MyActor {
 impl MyActor {
     fn run(ctx: Context, actor_id: &'static str, value: u32) -> TaskId {
         let mut value_in = ctx.actor_receiver::<Value>(actor_id);
+        let stop_in = ctx.actor_stop_receiver(actor_id);
         let mut actor = MyActor { value };
 
         println!("run the {actor_id}");
         ctx.clone().spawn(async move {
-            truba::event_loop!(ctx, {
+            truba::actor_event_loop!(ctx, stop_in = stop_in, {
                 Some(msg) = value_in.recv() => {
                     actor.handle_value(msg);
-                    if actor.value == 0 {
-                        break;
-                    }
                 },
             });
             println!("stop the {actor_id}");
@@ -50,7 +48,7 @@ async fn main() {
     sender_2.send(Value(21)).await.ok();
     sender_2.send(Value(22)).await.ok();
 
-    sender_2.send(Value(0)).await.ok();
+    ctx.stop_actor("actor 2");
     ctx.join(&actor_2_task).await.ok();
 
     MyActor::run(ctx.clone(), "actor 2", 2);
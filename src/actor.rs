@@ -0,0 +1,281 @@
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::ops::ControlFlow;
+
+use tokio::task::JoinError;
+
+use crate::system::ActorStop;
+use crate::{ActorRef, Context, Message, Receiver, TaskId};
+
+pub trait Actor: Send + Sized + 'static {
+    type Id: Eq + Hash + fmt::Display + Clone + Send + Sync + 'static;
+    type Inbox: Send + 'static;
+    type Message: Send;
+
+    fn inbox(ctx: &Context<Self::Id>, id: &Self::Id) -> Self::Inbox;
+
+    /// Receives the next message, `None` stops the actor. Must be cancel safe, as it races with the stop signals.
+    fn receive(inbox: &mut Self::Inbox) -> impl Future<Output = Option<Self::Message>> + Send;
+
+    /// Handles the received message to completion, the stop signals are not observed meanwhile.
+    fn handle(&mut self, message: Self::Message) -> impl Future<Output = ControlFlow<()>> + Send;
+
+    fn started(&mut self, _ctx: &Context<Self::Id>) {}
+
+    fn stopping(&mut self, _inbox: &mut Self::Inbox) {}
+
+    fn stopped(self) {}
+}
+
+pub struct ActorHandle<A: Actor> {
+    id: A::Id,
+    task_id: TaskId,
+    ctx: Context<A::Id>,
+}
+
+impl<A: Actor> Clone for ActorHandle<A> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            task_id: self.task_id,
+            ctx: self.ctx.clone(),
+        }
+    }
+}
+
+impl<A: Actor> ActorHandle<A> {
+    pub(crate) fn new(id: A::Id, task_id: TaskId, ctx: Context<A::Id>) -> Self {
+        Self { id, task_id, ctx }
+    }
+
+    pub fn id(&self) -> &A::Id {
+        &self.id
+    }
+
+    pub fn task_id(&self) -> TaskId {
+        self.task_id
+    }
+
+    pub fn context(&self) -> &Context<A::Id> {
+        &self.ctx
+    }
+
+    pub fn actor_ref<M: Message>(&self) -> ActorRef<M, A::Id> {
+        self.ctx.actor_ref::<M>(self.id.clone())
+    }
+
+    pub fn stop(&self) {
+        self.ctx.stop_actor(self.id.clone())
+    }
+
+    pub fn cancel(&self) -> bool {
+        self.ctx.cancel(&self.task_id)
+    }
+
+    pub fn abort(&self) -> bool {
+        self.ctx.abort(&self.task_id)
+    }
+
+    pub async fn join(&self) -> Result<(), JoinError> {
+        self.ctx.join(&self.task_id).await
+    }
+}
+
+pub(crate) async fn run<A: Actor>(
+    ctx: Context<A::Id>,
+    stop_in: Receiver<ActorStop>,
+    mut actor: A,
+    mut inbox: A::Inbox,
+) {
+    actor.started(&ctx);
+
+    crate::actor_event_loop!(ctx, stop_in = stop_in, {
+        message = A::receive(&mut inbox) => {
+            let Some(message) = message else {
+                break;
+            };
+            if actor.handle(message).await.is_break() {
+                break;
+            }
+        },
+    });
+
+    actor.stopping(&mut inbox);
+    drop(inbox);
+    actor.stopped();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use std::time::Duration;
+
+    use tokio::sync::Notify;
+    use tokio::time;
+
+    use crate::{Actor, Context, Message, MpscChannel, Receiver, TaskState};
+
+    struct Value(u32);
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+    }
+
+    struct Counter {
+        sum: Arc<AtomicU32>,
+        handling: Option<Arc<Notify>>,
+    }
+
+    impl Counter {
+        fn new(sum: &Arc<AtomicU32>) -> Self {
+            Self {
+                sum: sum.clone(),
+                handling: None,
+            }
+        }
+    }
+
+    impl Actor for Counter {
+        type Id = i32;
+        type Inbox = Receiver<Value>;
+        type Message = Value;
+
+        fn inbox(ctx: &Context<i32>, id: &i32) -> Self::Inbox {
+            ctx.actor_receiver::<Value>(*id)
+        }
+
+        async fn receive(inbox: &mut Self::Inbox) -> Option<Value> {
+            inbox.recv().await
+        }
+
+        async fn handle(&mut self, Value(value): Value) -> ControlFlow<()> {
+            if value == 0 {
+                return ControlFlow::Break(());
+            }
+            if let Some(handling) = &self.handling {
+                handling.notify_one();
+                time::sleep(Duration::from_millis(10)).await;
+            }
+            self.sum.fetch_add(value, Ordering::SeqCst);
+            ControlFlow::Continue(())
+        }
+
+        fn stopped(self) {
+            self.sum.fetch_add(100, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn start_and_stop_actor() {
+        let ctx = Context::<i32>::new();
+        let sum = Arc::new(AtomicU32::new(0));
+
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        assert_eq!(*handle.id(), 1);
+
+        let sender = ctx.actor_sender::<Value>(1);
+        sender.send(Value(1)).await.ok().unwrap();
+        sender.send(Value(2)).await.ok().unwrap();
+        sender.send(Value(0)).await.ok().unwrap();
+        handle.join().await.unwrap();
+
+        assert_eq!(sum.load(Ordering::SeqCst), 103);
+
+        ctx.start_actor(1, Counter::new(&sum));
+        tokio::task::yield_now().await;
+        ctx.shutdown().await;
+
+        assert_eq!(sum.load(Ordering::SeqCst), 203);
+    }
+
+    #[tokio::test]
+    async fn stop_actor() {
+        let ctx = Context::<i32>::new();
+        let sum = Arc::new(AtomicU32::new(0));
+
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        tokio::task::yield_now().await;
+        handle.stop();
+        handle.join().await.unwrap();
+        assert_eq!(sum.load(Ordering::SeqCst), 100);
+
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        ctx.actor_sender::<Value>(1).send(Value(5)).await.ok().unwrap();
+        tokio::task::yield_now().await;
+        ctx.stop_actor(1);
+        handle.join().await.unwrap();
+        assert_eq!(sum.load(Ordering::SeqCst), 205);
+    }
+
+    #[tokio::test]
+    async fn stop_running_loops_only() {
+        let ctx = Context::<i32>::new();
+        let sum = Arc::new(AtomicU32::new(0));
+
+        ctx.stop_actor(1);
+        assert!(ctx.system().actor_ids().is_empty());
+
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        tokio::task::yield_now().await;
+        handle.stop();
+        handle.join().await.unwrap();
+
+        ctx.stop_actor(1);
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        let twin = ctx.spawn({
+            let ctx = ctx.clone();
+            async move { crate::actor_event_loop!(ctx, 1, {}) }
+        });
+        tokio::task::yield_now().await;
+        let running = |id| ctx.handles().get(&id).unwrap().state == TaskState::Running;
+        assert!(running(handle.task_id()) && running(twin));
+
+        handle.stop();
+        handle.join().await.unwrap();
+        ctx.join(&twin).await.unwrap();
+        assert_eq!(sum.load(Ordering::SeqCst), 200);
+        assert!(ctx.system().validate().receivers_taken_again.is_empty());
+
+        let handle = ctx.start_actor(1, Counter::new(&sum));
+        handle.stop();
+        handle.join().await.unwrap();
+        assert_eq!(sum.load(Ordering::SeqCst), 300);
+    }
+
+    #[tokio::test]
+    async fn prune_stopped_actor() {
+        let ctx = Context::<i32>::new();
+        let handle = ctx.start_actor(1, Counter::new(&Arc::new(AtomicU32::new(0))));
+        tokio::task::yield_now().await;
+        assert!(ctx.prune_closed().is_empty());
+
+        handle.stop();
+        handle.join().await.unwrap();
+        assert_eq!(ctx.prune_closed(), [1]);
+        assert!(ctx.system().actor_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn finish_handling_on_shutdown() {
+        let ctx = Context::<i32>::new();
+        let sum = Arc::new(AtomicU32::new(0));
+        let handling = Arc::new(Notify::new());
+
+        ctx.start_actor(
+            1,
+            Counter {
+                handling: Some(handling.clone()),
+                ..Counter::new(&sum)
+            },
+        );
+        ctx.actor_sender::<Value>(1).send(Value(7)).await.ok().unwrap();
+        handling.notified().await;
+        ctx.shutdown().await;
+
+        assert_eq!(sum.load(Ordering::SeqCst), 107);
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::future::{self, Future};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::{SendError, SendTimeoutError, TrySendError};
+
+use crate::{
+    AskError, Context, DefaultActorId, Message, MpmcSender, MpscChannel, MpscSender, OverflowSender, PrioritySender,
+    Request, RequestChannel, RequestSender, Sender, UnboundedMpscSender,
+};
+
+pub trait ClosableSender: Clone {
+    fn is_closed(&self) -> bool;
+}
+
+impl<T> ClosableSender for MpscSender<T> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+impl<T> ClosableSender for UnboundedMpscSender<T> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+impl<T> ClosableSender for MpmcSender<T> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+impl<T> ClosableSender for OverflowSender<T> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+impl<T> ClosableSender for PrioritySender<T> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+impl<M: Request> ClosableSender for RequestSender<M> {
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+/// The senders of the channels delivering every message to a single receiver.
+pub trait MessageSender<T>: ClosableSender {
+    type Error;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>>;
+}
+
+impl<T: Send> MessageSender<T> for MpscSender<T> {
+    type Error = SendError<T>;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.send(message)
+    }
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<T: Send> MessageSender<T> for UnboundedMpscSender<T> {
+    type Error = SendError<T>;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        future::ready(self.send(message))
+    }
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.send(message)
+            .map_err(|SendError(message)| TrySendError::Closed(message))
+    }
+}
+
+impl<T: Send> MessageSender<T> for MpmcSender<T> {
+    type Error = SendError<T>;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.send(message)
+    }
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<T: Send> MessageSender<T> for OverflowSender<T> {
+    type Error = TrySendError<T>;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.send(message)
+    }
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<T: Send> MessageSender<T> for PrioritySender<T> {
+    type Error = SendError<T>;
+
+    fn send(&self, message: T) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.send(message)
+    }
+
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+pub struct ActorRef<M: Message, ActorId = DefaultActorId> {
+    actor_id: ActorId,
+    ctx: Context<ActorId>,
+    sender: Arc<Mutex<Option<Sender<M>>>>,
+}
+
+impl<M: Message, ActorId: Clone> Clone for ActorRef<M, ActorId> {
+    fn clone(&self) -> Self {
+        Self {
+            actor_id: self.actor_id.clone(),
+            ctx: self.ctx.clone(),
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<M: Message, ActorId> ActorRef<M, ActorId> {
+    pub fn new(ctx: Context<ActorId>, actor_id: ActorId) -> Self {
+        Self {
+            actor_id,
+            ctx,
+            sender: Default::default(),
+        }
+    }
+
+    pub fn id(&self) -> &ActorId {
+        &self.actor_id
+    }
+
+    pub fn context(&self) -> &Context<ActorId> {
+        &self.ctx
+    }
+}
+
+impl<M, ActorId> ActorRef<M, ActorId>
+where
+    M: Message,
+    Sender<M>: ClosableSender,
+    ActorId: Eq + Hash + fmt::Display + Clone,
+{
+    pub fn sender(&self) -> Sender<M> {
+        let mut cached = self.sender.lock();
+        match &*cached {
+            Some(sender) if !sender.is_closed() => sender.clone(),
+            Some(sender) => {
+                // Pick up the channel recreated in the registry, if any, otherwise keep the closed one
+                let sender = self
+                    .ctx
+                    .get_actor_sender::<M>(&self.actor_id)
+                    .filter(|actual| !actual.is_closed())
+                    .unwrap_or_else(|| sender.clone());
+                *cached = Some(sender.clone());
+                sender
+            },
+            None => {
+                let sender = self.ctx.actor_sender::<M>(self.actor_id.clone());
+                *cached = Some(sender.clone());
+                sender
+            },
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender().is_closed()
+    }
+}
+
+impl<M, ActorId> ActorRef<M, ActorId>
+where
+    M: Message,
+    Sender<M>: MessageSender<M>,
+    ActorId: Eq + Hash + fmt::Display + Clone,
+{
+    pub async fn send(&self, message: M) -> Result<(), <Sender<M> as MessageSender<M>>::Error> {
+        MessageSender::send(&self.sender(), message).await
+    }
+
+    pub fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        MessageSender::try_send(&self.sender(), message)
+    }
+}
+
+impl<T, ActorId> ActorRef<T, ActorId>
+where
+    T: Message<Channel = MpscChannel<T>> + Send,
+    ActorId: Eq + Hash + fmt::Display + Clone,
+{
+    pub async fn send_timeout(&self, message: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.sender().send_timeout(message, timeout).await
+    }
+}
+
+impl<M, ActorId> ActorRef<M, ActorId>
+where
+    M: Request + Message<Channel = RequestChannel<M>>,
+    ActorId: Eq + Hash + fmt::Display + Clone,
+{
+    pub async fn ask(&self, request: M) -> Result<M::Response, AskError> {
+        self.sender().ask(request).await
+    }
+
+    pub async fn ask_timeout(&self, request: M, timeout: Duration) -> Result<M::Response, AskError> {
+        self.sender().ask_timeout(request, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::error::{SendError, SendTimeoutError};
+
+    use crate::{Context, Message, MpscChannel, Overflow, OverflowChannel, UnboundedMpscChannel};
+
+    #[derive(Debug, PartialEq)]
+    struct Value(u32);
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+
+        fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+            MpscChannel::new(1)
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_recreated_channel() {
+        let ctx = Context::<i32>::new();
+        let actor_ref = ctx.actor_ref::<Value>(1);
+
+        let mut value_in = ctx.actor_receiver::<Value>(1);
+        actor_ref.clone().send(Value(1)).await.unwrap();
+        assert_eq!(value_in.recv().await, Some(Value(1)));
+
+        actor_ref.try_send(Value(2)).unwrap();
+        assert!(matches!(
+            actor_ref.send_timeout(Value(3), Duration::from_millis(1)).await,
+            Err(SendTimeoutError::Timeout(Value(3)))
+        ));
+
+        drop(value_in);
+        assert!(actor_ref.is_closed());
+        assert_eq!(actor_ref.send(Value(4)).await, Err(SendError(Value(4))));
+
+        let mut value_in = ctx.actor_receiver::<Value>(1);
+        assert!(!actor_ref.is_closed());
+        actor_ref.send(Value(5)).await.unwrap();
+        assert_eq!(value_in.recv().await, Some(Value(5)));
+    }
+
+    #[tokio::test]
+    async fn send_to_any_inbox() {
+        #[derive(Debug, PartialEq)]
+        struct Event(u32);
+
+        impl Message for Event {
+            type Channel = UnboundedMpscChannel<Self>;
+        }
+
+        #[derive(Debug, PartialEq)]
+        struct Tick(u32);
+
+        impl Message for Tick {
+            type Channel = OverflowChannel<Self>;
+
+            fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+                OverflowChannel::new(1, Overflow::DropNewest)
+            }
+        }
+
+        let ctx = Context::<i32>::new();
+        let mut event_in = ctx.actor_receiver::<Event>(1);
+        let mut tick_in = ctx.actor_receiver::<Tick>(1);
+
+        ctx.actor_ref::<Event>(1).send(Event(1)).await.unwrap();
+        ctx.actor_ref::<Event>(1).try_send(Event(2)).unwrap();
+        assert_eq!(event_in.recv().await, Some(Event(1)));
+        assert_eq!(event_in.recv().await, Some(Event(2)));
+
+        let tick_ref = ctx.actor_ref::<Tick>(1);
+        tick_ref.send(Tick(1)).await.unwrap();
+        tick_ref.try_send(Tick(2)).unwrap();
+        assert_eq!(tick_in.recv().await, Some(Tick(1)));
+        assert!(tick_in.try_recv().is_err());
+    }
+}
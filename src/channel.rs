@@ -1,17 +1,65 @@
 pub use self::broadcast::BroadcastChannel;
-pub use self::mpsc::{MpscChannel, UnboundedMpscChannel};
+pub use self::mpmc::{MpmcChannel, MpmcReceiver, MpmcSender};
+pub use self::mpsc::{
+    MpscChannel, MpscReceiver, MpscSender, UnboundedMpscChannel, UnboundedMpscReceiver, UnboundedMpscSender,
+};
+pub use self::overflow::{Overflow, OverflowChannel, OverflowReceiver, OverflowSender};
+pub use self::priority::{Prioritized, PriorityChannel, PriorityReceiver, PrioritySender};
+pub use self::request::{AskError, Request, RequestChannel, RequestReceiver, RequestSender, Responder};
 pub use self::watch::WatchChannel;
 
+use crate::{ChannelCounters, SystemConfig};
+
 pub mod broadcast;
+pub mod mpmc;
 pub mod mpsc;
+pub mod overflow;
+pub mod priority;
+mod queue;
+pub mod request;
 pub mod watch;
 
-pub trait Channel: Send {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChannelKind {
+    Mpsc,
+    UnboundedMpsc,
+    Mpmc,
+    Broadcast,
+    Watch,
+    Request,
+    Priority,
+    Overflow,
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelInfo {
+    pub message: &'static str,
+    pub kind: ChannelKind,
+    pub capacity: Option<usize>,
+    pub queue_len: Option<usize>,
+    pub is_closed: bool,
+    pub receiver_taken: Option<bool>,
+    pub senders: Option<usize>,
+}
+
+pub trait Channel: Send + Sync {
     type Sender;
     type Receiver;
 
+    const KIND: ChannelKind = ChannelKind::Custom;
+
     fn create() -> Self;
 
+    /// Replaces the just created channel with the one of the given capacity, keeping the rest of its configuration.
+    /// Channels without a capacity return themselves.
+    fn with_capacity(self, _capacity: usize) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
     fn actor_create(_actor_id: impl Into<String>) -> Self
     where
         Self: Sized,
@@ -21,9 +69,48 @@ pub trait Channel: Send {
 
     fn sender(&self) -> Self::Sender;
 
+    /// Takes a sender, unless the single one was already handed out. Channels with multiple senders always return it.
+    fn try_sender(&self) -> Option<Self::Sender> {
+        Some(self.sender())
+    }
+
     fn receiver(&self) -> Self::Receiver;
 
+    /// Takes the receiver, unless it was already handed out. Channels with multiple receivers always return it.
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        Some(self.receiver())
+    }
+
     fn is_closed(&self) -> bool;
+
+    /// Reopens a closed channel in place, keeping existing senders able to follow it.
+    /// Returns `false` if not supported, in which case the registry replaces the channel.
+    fn reopen(&self) -> bool {
+        false
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// The number of messages waiting to be received, if the channel is able to tell.
+    fn queue_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        None
+    }
+
+    /// Whether the single receiver of the channel was handed out, if the channel has a single one.
+    fn is_receiver_taken(&self) -> Option<bool> {
+        None
+    }
+
+    /// The number of senders handed out and still alive, if the channel is able to tell.
+    fn sender_count(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub trait Message: 'static {
@@ -36,6 +123,30 @@ pub trait Message: 'static {
     fn create_actor_channel(actor_id: impl Into<String>) -> Self::Channel {
         Self::Channel::actor_create(actor_id)
     }
+
+    /// Creates the channel as `create_channel` does, with the default capacities of the config,
+    /// and resizes it to the capacity configured for this message, if any.
+    fn create_channel_with(config: &SystemConfig) -> Self::Channel
+    where
+        Self: Sized,
+    {
+        let channel = config.scope(Self::create_channel);
+        match config.message_capacity_of::<Self>() {
+            Some(capacity) => channel.with_capacity(capacity),
+            None => channel,
+        }
+    }
+
+    fn create_actor_channel_with(actor_id: impl Into<String>, config: &SystemConfig) -> Self::Channel
+    where
+        Self: Sized,
+    {
+        let channel = config.scope(|| Self::create_actor_channel(actor_id));
+        match config.message_capacity_of::<Self>() {
+            Some(capacity) => channel.with_capacity(capacity),
+            None => channel,
+        }
+    }
 }
 
 pub type Sender<T> = <<T as Message>::Channel as Channel>::Sender;
@@ -1,20 +1,17 @@
-use std::sync::Arc;
-
-use parking_lot::Mutex;
 use tokio::sync::broadcast;
 
-use crate::Channel;
+use crate::system::config::default_capacity;
+use crate::{Channel, ChannelKind};
 
 pub struct BroadcastChannel<T> {
-    sender: Arc<Mutex<broadcast::Sender<T>>>,
+    buffer: usize,
+    sender: broadcast::Sender<T>,
 }
 
 impl<T: Send + Clone> BroadcastChannel<T> {
     pub fn new(buffer: usize) -> Self {
         let (sender, _receiver) = broadcast::channel(buffer);
-        Self {
-            sender: Arc::new(Mutex::new(sender)),
-        }
+        Self { buffer, sender }
     }
 }
 
@@ -22,19 +19,47 @@ impl<T: Send + Clone> Channel for BroadcastChannel<T> {
     type Sender = broadcast::Sender<T>;
     type Receiver = broadcast::Receiver<T>;
 
+    const KIND: ChannelKind = ChannelKind::Broadcast;
+
     fn create() -> Self {
-        Self::new(1024)
+        Self::new(default_capacity(Self::KIND, 1024))
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity)
     }
 
     fn sender(&self) -> Self::Sender {
-        self.sender.lock().clone()
+        self.sender.clone()
     }
 
     fn receiver(&self) -> Self::Receiver {
-        self.sender.lock().subscribe()
+        self.sender.subscribe()
     }
 
+    // Closed while nobody is subscribed, as the sends fail then
     fn is_closed(&self) -> bool {
-        false
+        self.sender.receiver_count() == 0
+    }
+
+    // Subscribing reopens it, and the senders keep following the same channel
+    fn reopen(&self) -> bool {
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.buffer)
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        Some(self.sender.len())
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.sender.receiver_count() > 0)
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(self.sender.strong_count() - 1)
     }
 }
@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use tokio::sync::mpsc::error::{SendError, TryRecvError, TrySendError};
+
+use crate::channel::overflow::Overflow;
+use crate::channel::queue::{self, Shared};
+use crate::system::config::default_capacity;
+use crate::{Channel, ChannelCounters, ChannelKind};
+
+pub struct MpmcSender<T> {
+    inner: queue::Sender<VecDeque<T>>,
+}
+
+impl<T> MpmcSender<T> {
+    /// Sends the message, waiting for space while the queue is full.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.inner.send(value).await.map_err(|err| SendError(err.into_inner()))
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(value)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity().saturating_sub(self.inner.len())
+    }
+
+    pub fn max_capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+}
+
+impl<T> Clone for MpmcSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// One of the competing consumers: every message is received by a single receiver.
+pub struct MpmcReceiver<T> {
+    inner: queue::Receiver<VecDeque<T>>,
+}
+
+impl<T> MpmcReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        self.inner.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Clone for MpmcReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A bounded work queue shared by any number of receivers. The channel is closed when the last receiver drops,
+/// and the messages sent before the first receiver is taken wait in the queue.
+pub struct MpmcChannel<T> {
+    shared: Arc<Shared<VecDeque<T>>>,
+    sender: MpmcSender<T>,
+}
+
+impl<T> MpmcChannel<T> {
+    pub fn new(capacity: usize) -> Self {
+        let shared = Shared::new(VecDeque::new(), capacity, Overflow::Block);
+        Self {
+            sender: MpmcSender {
+                inner: queue::Sender::new(shared.clone()),
+            },
+            shared,
+        }
+    }
+
+    pub fn into_inner(self) -> (MpmcSender<T>, MpmcReceiver<T>) {
+        let receiver = MpmcReceiver {
+            inner: queue::Receiver::new(self.shared),
+        };
+        (self.sender, receiver)
+    }
+}
+
+impl<T: Send> Channel for MpmcChannel<T> {
+    type Sender = MpmcSender<T>;
+    type Receiver = MpmcReceiver<T>;
+
+    const KIND: ChannelKind = ChannelKind::Mpmc;
+
+    fn create() -> Self {
+        Self::new(default_capacity(Self::KIND, 1024))
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity)
+    }
+
+    fn sender(&self) -> Self::Sender {
+        self.sender.clone()
+    }
+
+    fn receiver(&self) -> Self::Receiver {
+        MpmcReceiver {
+            inner: queue::Receiver::new(self.shared.clone()),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+
+    // The senders share the queue with the channel, so they follow it without being replaced
+    fn reopen(&self) -> bool {
+        self.shared.reopen();
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.shared.capacity())
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        Some(self.shared.len())
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        Some(self.shared.counters())
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.shared.has_receivers())
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(self.shared.sender_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use tokio::sync::mpsc::error::SendError;
+
+    use crate::channel::mpmc::MpmcChannel;
+    use crate::{Channel, Context, Message};
+
+    #[derive(Debug, PartialEq)]
+    struct Job(u32);
+
+    impl Message for Job {
+        type Channel = MpmcChannel<Self>;
+    }
+
+    #[tokio::test]
+    async fn competing_workers() {
+        let (job_out, job_in) = MpmcChannel::new(100).into_inner();
+        for id in 0..100 {
+            job_out.send(Job(id)).await.unwrap();
+        }
+        drop(job_out);
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut job_in = job_in.clone();
+                tokio::spawn(async move {
+                    let mut done = Vec::new();
+                    while let Some(job) = job_in.recv().await {
+                        done.push(job.0);
+                    }
+                    done
+                })
+            })
+            .collect();
+        drop(job_in);
+
+        let mut done = BTreeSet::new();
+        for worker in workers {
+            for id in worker.await.unwrap() {
+                assert!(done.insert(id));
+            }
+        }
+        assert_eq!(done, (0..100).collect());
+    }
+
+    #[tokio::test]
+    async fn close_with_last_receiver() {
+        let ctx = Context::<i32>::new();
+        let job_out = ctx.sender::<Job>();
+        let job_in = ctx.receiver::<Job>();
+        job_out.send(Job(0)).await.unwrap();
+
+        drop(job_in.clone());
+        assert_eq!(ctx.is_channel_closed::<Job>(), Some(false));
+        drop(job_in);
+        assert_eq!(ctx.is_channel_closed::<Job>(), Some(true));
+        assert_eq!(ctx.system().get_channel::<Job>().unwrap().queue_len(), Some(0));
+        assert_eq!(job_out.send(Job(1)).await, Err(SendError(Job(1))));
+
+        let _job_in = ctx.receiver::<Job>();
+        job_out.send(Job(2)).await.unwrap();
+    }
+}
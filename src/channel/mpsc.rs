@@ -1,58 +1,314 @@
 use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
-use parking_lot::Mutex;
+use futures::Stream;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendError, SendTimeoutError, TryRecvError, TrySendError};
 
-use crate::Channel;
+use crate::system::config::default_capacity;
+use crate::{trace, Channel, ChannelCounters, ChannelKind};
+
+struct Slot<S, R> {
+    sender: RwLock<S>,
+    receiver: Mutex<Option<R>>,
+    detached: AtomicBool,
+    counters: Arc<ChannelCounters>,
+}
+
+impl<S, R> Slot<S, R> {
+    fn new(sender: S, receiver: R) -> Self {
+        Self {
+            sender: RwLock::new(sender),
+            receiver: Mutex::new(Some(receiver)),
+            detached: AtomicBool::new(false),
+            counters: Default::default(),
+        }
+    }
+
+    // Leaves a closed channel behind, so the senders which follow the slot do not send into the void
+    fn detach(&self, closed_sender: S) -> (S, Option<R>) {
+        let mut sender = self.sender.write();
+        self.detached.store(true, Ordering::Release);
+        (mem::replace(&mut *sender, closed_sender), self.receiver.lock().take())
+    }
+
+    fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::Acquire)
+    }
+}
+
+type BoundedSlot<T> = Slot<mpsc::Sender<T>, mpsc::Receiver<T>>;
+type UnboundedSlot<T> = Slot<mpsc::UnboundedSender<T>, mpsc::UnboundedReceiver<T>>;
 
 pub struct MpscChannel<T> {
-    sender: Arc<Mutex<mpsc::Sender<T>>>,
-    receiver: Arc<Mutex<Option<mpsc::Receiver<T>>>>,
+    buffer: usize,
+    slot: Arc<BoundedSlot<T>>,
 }
 
 impl<T> MpscChannel<T> {
     pub fn new(buffer: usize) -> Self {
         let (sender, receiver) = mpsc::channel(buffer);
         Self {
-            sender: Arc::new(Mutex::new(sender)),
-            receiver: Arc::new(Mutex::new(Some(receiver))),
+            buffer,
+            slot: Arc::new(Slot::new(sender, receiver)),
         }
     }
 
     pub fn into_inner(self) -> (mpsc::Sender<T>, Option<mpsc::Receiver<T>>) {
-        let (sender, receiver) = mpsc::channel(1);
-        (
-            mem::replace(&mut *self.sender.lock(), sender),
-            mem::replace(&mut *self.receiver.lock(), Some(receiver)),
-        )
+        self.slot.detach(mpsc::channel(1).0)
+    }
+}
+
+fn reopen_bounded<T>(slot: &BoundedSlot<T>, buffer: usize) {
+    let mut sender = slot.sender.write();
+    if sender.is_closed() && !slot.is_detached() {
+        let (new_sender, receiver) = mpsc::channel(buffer);
+        *sender = new_sender;
+        *slot.receiver.lock() = Some(receiver);
     }
 }
 
 impl<T: Send> Channel for MpscChannel<T> {
-    type Sender = mpsc::Sender<T>;
-    type Receiver = mpsc::Receiver<T>;
+    type Sender = MpscSender<T>;
+    type Receiver = MpscReceiver<T>;
+
+    const KIND: ChannelKind = ChannelKind::Mpsc;
 
     fn create() -> Self {
-        Self::new(1024)
+        Self::new(default_capacity(Self::KIND, 1024))
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity)
     }
 
     fn sender(&self) -> Self::Sender {
-        self.sender.lock().clone()
+        MpscSender {
+            sender: Mutex::new(self.slot.sender.read().clone()),
+            slot: self.slot.clone(),
+            buffer: self.buffer,
+            buffer_while_closed: false,
+        }
     }
 
     fn receiver(&self) -> Self::Receiver {
-        self.receiver.lock().take().unwrap_or_else(|| mpsc::channel(1).1)
+        self.try_receiver().unwrap_or_else(|| MpscReceiver {
+            receiver: mpsc::channel(1).1,
+            counters: Default::default(),
+        })
+    }
+
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        let receiver = self.slot.receiver.lock().take()?;
+        Some(MpscReceiver {
+            receiver,
+            counters: self.slot.counters.clone(),
+        })
     }
 
     fn is_closed(&self) -> bool {
-        self.sender.lock().is_closed()
+        self.slot.sender.read().is_closed()
+    }
+
+    fn reopen(&self) -> bool {
+        reopen_bounded(&self.slot, self.buffer);
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.buffer)
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        let sender = self.slot.sender.read();
+        Some(sender.max_capacity() - sender.capacity())
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        Some(&self.slot.counters)
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.slot.receiver.lock().is_none())
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(Arc::strong_count(&self.slot) - 1)
+    }
+}
+
+/// A sender which follows the channel when it is reopened in the registry, e.g. after an actor restart.
+pub struct MpscSender<T> {
+    sender: Mutex<mpsc::Sender<T>>,
+    slot: Arc<BoundedSlot<T>>,
+    buffer: usize,
+    buffer_while_closed: bool,
+}
+
+impl<T> Clone for MpscSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: Mutex::new(self.sender.lock().clone()),
+            slot: self.slot.clone(),
+            buffer: self.buffer,
+            buffer_while_closed: self.buffer_while_closed,
+        }
+    }
+}
+
+impl<T> MpscSender<T> {
+    /// Reopens the closed channel on send, so messages wait for the next receiver instead of failing.
+    pub fn buffer_while_closed(mut self) -> Self {
+        self.buffer_while_closed = true;
+        self
+    }
+
+    pub fn inner(&self) -> mpsc::Sender<T> {
+        let mut sender = self.sender.lock();
+        if sender.is_closed() {
+            let actual = self.slot.sender.read();
+            if !actual.same_channel(&sender) {
+                *sender = actual.clone();
+            }
+        }
+        sender.clone()
+    }
+
+    fn reopened(&self) -> Option<mpsc::Sender<T>> {
+        if self.buffer_while_closed {
+            reopen_bounded(&self.slot, self.buffer);
+            Some(self.inner())
+        } else {
+            None
+        }
+    }
+
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let _blocking = self.slot.counters.start_blocking();
+        let result = match self.inner().send(value).await {
+            Err(SendError(value)) => match self.reopened() {
+                Some(sender) => sender.send(value).await,
+                None => Err(SendError(value)),
+            },
+            result => result,
+        };
+        self.slot.counters.record(&result);
+        trace::sent::<T, _, _>(&result);
+        result
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let result = match self.inner().try_send(value) {
+            Err(TrySendError::Closed(value)) => match self.reopened() {
+                Some(sender) => sender.try_send(value),
+                None => Err(TrySendError::Closed(value)),
+            },
+            result => result,
+        };
+        self.slot.counters.record(&result);
+        trace::sent::<T, _, _>(&result);
+        result
+    }
+
+    pub async fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let _blocking = self.slot.counters.start_blocking();
+        let result = match self.inner().send_timeout(value, timeout).await {
+            Err(SendTimeoutError::Closed(value)) => match self.reopened() {
+                Some(sender) => sender.send_timeout(value, timeout).await,
+                None => Err(SendTimeoutError::Closed(value)),
+            },
+            result => result,
+        };
+        self.slot.counters.record(&result);
+        trace::sent::<T, _, _>(&result);
+        result
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner().is_closed()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner().capacity()
+    }
+
+    pub fn max_capacity(&self) -> usize {
+        self.inner().max_capacity()
+    }
+}
+
+/// The receiver of [`MpscChannel`], counting and tracing the received messages. It replaces tokio's `mpsc::Receiver`
+/// returned before, and is a [`Stream`] itself where a `ReceiverStream` wrapped that one. [`Self::into_inner`] gives
+/// the tokio receiver back, which receives without counting and tracing.
+pub struct MpscReceiver<T> {
+    receiver: mpsc::Receiver<T>,
+    counters: Arc<ChannelCounters>,
+}
+
+impl<T> MpscReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        let received = self.receiver.recv().await;
+        if received.is_some() {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let received = self.receiver.try_recv();
+        if received.is_ok() {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let received = self.receiver.poll_recv(cx);
+        if let Poll::Ready(Some(_)) = received {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn close(&mut self) {
+        self.receiver.close()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.receiver.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    pub fn into_inner(self) -> mpsc::Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for MpscReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
     }
 }
 
 pub struct UnboundedMpscChannel<T> {
-    sender: Arc<Mutex<mpsc::UnboundedSender<T>>>,
-    receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<T>>>>,
+    slot: Arc<UnboundedSlot<T>>,
 }
 
 impl<T: Send> Default for UnboundedMpscChannel<T> {
@@ -65,51 +321,211 @@ impl<T: Send> UnboundedMpscChannel<T> {
     pub fn new() -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
         Self {
-            sender: Arc::new(Mutex::new(sender)),
-            receiver: Arc::new(Mutex::new(Some(receiver))),
+            slot: Arc::new(Slot::new(sender, receiver)),
         }
     }
 
     pub fn into_inner(self) -> (mpsc::UnboundedSender<T>, Option<mpsc::UnboundedReceiver<T>>) {
-        let (sender, receiver) = mpsc::unbounded_channel();
-        (
-            mem::replace(&mut *self.sender.lock(), sender),
-            mem::replace(&mut *self.receiver.lock(), Some(receiver)),
-        )
+        self.slot.detach(mpsc::unbounded_channel().0)
+    }
+}
+
+fn reopen_unbounded<T>(slot: &UnboundedSlot<T>) {
+    let mut sender = slot.sender.write();
+    if sender.is_closed() && !slot.is_detached() {
+        let (new_sender, receiver) = mpsc::unbounded_channel();
+        *sender = new_sender;
+        *slot.receiver.lock() = Some(receiver);
     }
 }
 
 impl<T: Send> Channel for UnboundedMpscChannel<T> {
-    type Sender = mpsc::UnboundedSender<T>;
-    type Receiver = mpsc::UnboundedReceiver<T>;
+    type Sender = UnboundedMpscSender<T>;
+    type Receiver = UnboundedMpscReceiver<T>;
+
+    const KIND: ChannelKind = ChannelKind::UnboundedMpsc;
 
     fn create() -> Self {
         Self::new()
     }
 
     fn sender(&self) -> Self::Sender {
-        self.sender.lock().clone()
+        UnboundedMpscSender {
+            sender: Mutex::new(self.slot.sender.read().clone()),
+            slot: self.slot.clone(),
+            buffer_while_closed: false,
+        }
     }
 
     fn receiver(&self) -> Self::Receiver {
-        self.receiver
-            .lock()
-            .take()
-            .unwrap_or_else(|| mpsc::unbounded_channel().1)
+        self.try_receiver().unwrap_or_else(|| UnboundedMpscReceiver {
+            receiver: mpsc::unbounded_channel().1,
+            counters: Default::default(),
+        })
+    }
+
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        let receiver = self.slot.receiver.lock().take()?;
+        Some(UnboundedMpscReceiver {
+            receiver,
+            counters: self.slot.counters.clone(),
+        })
     }
 
     fn is_closed(&self) -> bool {
-        self.sender.lock().is_closed()
+        self.slot.sender.read().is_closed()
+    }
+
+    fn reopen(&self) -> bool {
+        reopen_unbounded(&self.slot);
+        true
+    }
+
+    // The length is only known while the receiver is not taken yet
+    fn queue_len(&self) -> Option<usize> {
+        self.slot.receiver.lock().as_ref().map(|receiver| receiver.len())
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        Some(&self.slot.counters)
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.slot.receiver.lock().is_none())
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(Arc::strong_count(&self.slot) - 1)
+    }
+}
+
+/// An unbounded counterpart of [`MpscSender`].
+pub struct UnboundedMpscSender<T> {
+    sender: Mutex<mpsc::UnboundedSender<T>>,
+    slot: Arc<UnboundedSlot<T>>,
+    buffer_while_closed: bool,
+}
+
+impl<T> Clone for UnboundedMpscSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: Mutex::new(self.sender.lock().clone()),
+            slot: self.slot.clone(),
+            buffer_while_closed: self.buffer_while_closed,
+        }
+    }
+}
+
+impl<T> UnboundedMpscSender<T> {
+    /// Reopens the closed channel on send, so messages wait for the next receiver instead of failing.
+    pub fn buffer_while_closed(mut self) -> Self {
+        self.buffer_while_closed = true;
+        self
+    }
+
+    pub fn inner(&self) -> mpsc::UnboundedSender<T> {
+        let mut sender = self.sender.lock();
+        if sender.is_closed() {
+            let actual = self.slot.sender.read();
+            if !actual.same_channel(&sender) {
+                *sender = actual.clone();
+            }
+        }
+        sender.clone()
+    }
+
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let result = match self.inner().send(value) {
+            Err(SendError(value)) if self.buffer_while_closed => {
+                reopen_unbounded(&self.slot);
+                self.inner().send(value)
+            },
+            result => result,
+        };
+        self.slot.counters.record(&result);
+        trace::sent::<T, _, _>(&result);
+        result
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner().is_closed()
+    }
+}
+
+/// The receiver of [`UnboundedMpscChannel`], counting and tracing the received messages. It replaces tokio's `mpsc::UnboundedReceiver`
+/// returned before, and is a [`Stream`] itself where a `ReceiverStream` wrapped that one. [`Self::into_inner`] gives
+/// the tokio receiver back, which receives without counting and tracing.
+pub struct UnboundedMpscReceiver<T> {
+    receiver: mpsc::UnboundedReceiver<T>,
+    counters: Arc<ChannelCounters>,
+}
+
+impl<T> UnboundedMpscReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        let received = self.receiver.recv().await;
+        if received.is_some() {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let received = self.receiver.try_recv();
+        if received.is_ok() {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let received = self.receiver.poll_recv(cx);
+        if let Poll::Ready(Some(_)) = received {
+            self.counters.record_received();
+            trace::received::<T>();
+        }
+        received
+    }
+
+    pub fn close(&mut self) {
+        self.receiver.close()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.receiver.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    pub fn into_inner(self) -> mpsc::UnboundedReceiver<T> {
+        self.receiver
+    }
+}
+
+impl<T> Stream for UnboundedMpscReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use futures::StreamExt;
     use tokio::sync::mpsc::error::SendError;
 
-    use crate::{DefaultContext, Message, UnboundedMpscChannel};
+    use crate::{Context, DefaultContext, Message, MpscChannel, UnboundedMpscChannel};
 
     #[tokio::test]
+    #[allow(clippy::redundant_pattern_matching)]
     async fn extract_unbounded_channel() {
         struct Value(&'static str);
 
@@ -166,4 +582,54 @@ mod tests {
         drop(receiver);
         assert!(matches!(sender.send(Value("closed")), Err(SendError(Value("closed")))));
     }
+
+    #[tokio::test]
+    async fn sender_follows_reopened_channel() {
+        struct Value(u32);
+
+        impl Message for Value {
+            type Channel = MpscChannel<Self>;
+        }
+
+        let ctx = Context::<i32>::new();
+        let sender = ctx.actor_sender::<Value>(1);
+
+        drop(ctx.actor_receiver::<Value>(1));
+        assert!(sender.is_closed());
+        assert!(matches!(sender.send(Value(1)).await, Err(SendError(Value(1)))));
+
+        let mut receiver = ctx.actor_receiver::<Value>(1);
+        assert!(!sender.is_closed());
+        sender.send(Value(2)).await.ok().unwrap();
+        assert_eq!(receiver.recv().await.unwrap().0, 2);
+
+        let buffered = sender.clone().buffer_while_closed();
+        drop(receiver);
+        buffered.send(Value(3)).await.ok().unwrap();
+        assert!(!sender.is_closed());
+
+        let mut receiver = ctx.actor_receiver::<Value>(1);
+        assert_eq!(receiver.recv().await.unwrap().0, 3);
+    }
+
+    #[tokio::test]
+    async fn receive_as_stream() {
+        struct Value(u32);
+
+        impl Message for Value {
+            type Channel = MpscChannel<Self>;
+        }
+
+        let ctx = Context::<i32>::new();
+        let value_out = ctx.sender::<Value>();
+        let value_in = ctx.receiver::<Value>();
+        for value in 1..=3 {
+            value_out.send(Value(value)).await.ok().unwrap();
+        }
+        drop(value_out);
+        drop(ctx.extract_channel::<Value>());
+
+        let values: Vec<_> = value_in.map(|Value(value)| value).collect().await;
+        assert_eq!(values, [1, 2, 3]);
+    }
 }
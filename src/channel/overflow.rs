@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+
+use crate::channel::queue::{self, Shared};
+use crate::system::config::default_capacity;
+use crate::{Channel, ChannelCounters, ChannelKind};
+
+/// What to do with a message sent to a full channel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Wait until the receiver frees some space.
+    #[default]
+    Block,
+    /// Drop the message being sent.
+    DropNewest,
+    /// Drop the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Return `TrySendError::Full` immediately.
+    Fail,
+    /// Queue the message anyway, beyond the capacity.
+    Spill,
+}
+
+pub struct OverflowSender<T> {
+    inner: queue::Sender<VecDeque<T>>,
+}
+
+impl<T> OverflowSender<T> {
+    /// Sends the message according to the overflow policy, waiting for space only with [`Overflow::Block`].
+    pub async fn send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.inner.send(value).await
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(value)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped()
+    }
+
+    pub fn spilled(&self) -> u64 {
+        self.inner.spilled()
+    }
+}
+
+impl<T> Clone for OverflowSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct OverflowReceiver<T> {
+    inner: queue::Receiver<VecDeque<T>>,
+}
+
+impl<T> OverflowReceiver<T> {
+    fn new(shared: Arc<Shared<VecDeque<T>>>) -> Self {
+        Self {
+            inner: queue::Receiver::new(shared),
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<T> {
+        self.inner.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    /// Closes the receiving half, the messages already queued can still be received.
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct OverflowChannel<T> {
+    shared: Arc<Shared<VecDeque<T>>>,
+    sender: OverflowSender<T>,
+    receiver: Mutex<Option<OverflowReceiver<T>>>,
+}
+
+impl<T> OverflowChannel<T> {
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        let shared = Shared::new(VecDeque::new(), capacity, overflow);
+        Self {
+            sender: OverflowSender {
+                inner: queue::Sender::new(shared.clone()),
+            },
+            receiver: Mutex::new(Some(OverflowReceiver::new(shared.clone()))),
+            shared,
+        }
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.shared.overflow()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped()
+    }
+
+    pub fn spilled(&self) -> u64 {
+        self.shared.spilled()
+    }
+
+    pub fn into_inner(self) -> (OverflowSender<T>, Option<OverflowReceiver<T>>) {
+        let receiver = self.receiver.lock().take();
+        (self.sender, receiver)
+    }
+}
+
+impl<T: Send> Channel for OverflowChannel<T> {
+    type Sender = OverflowSender<T>;
+    type Receiver = OverflowReceiver<T>;
+
+    const KIND: ChannelKind = ChannelKind::Overflow;
+
+    fn create() -> Self {
+        Self::new(default_capacity(Self::KIND, 1024), Overflow::default())
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity, self.overflow())
+    }
+
+    fn sender(&self) -> Self::Sender {
+        self.sender.clone()
+    }
+
+    fn receiver(&self) -> Self::Receiver {
+        self.try_receiver()
+            .unwrap_or_else(|| OverflowReceiver::new(Shared::new(VecDeque::new(), 1, self.overflow())))
+    }
+
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        self.receiver.lock().take()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+
+    // The senders share the queue with the channel, so they follow it once a new receiver is in place.
+    // A receiver closed by hand is still alive though, and the channel is replaced then
+    fn reopen(&self) -> bool {
+        let mut receiver = self.receiver.lock();
+        if self.shared.has_receivers() {
+            return false;
+        }
+        *receiver = Some(OverflowReceiver::new(self.shared.clone()));
+        self.shared.reopen();
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.shared.capacity())
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        Some(self.shared.len())
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        Some(self.shared.counters())
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.receiver.lock().is_none())
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(self.shared.sender_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+
+    use crate::channel::overflow::{Overflow, OverflowChannel};
+    use crate::{Context, Message};
+
+    #[derive(Debug, PartialEq)]
+    struct Tick(u32);
+
+    impl Message for Tick {
+        type Channel = OverflowChannel<Self>;
+
+        fn create_channel() -> Self::Channel {
+            OverflowChannel::new(2, Overflow::DropOldest)
+        }
+    }
+
+    async fn drain(overflow: Overflow) -> (Vec<u32>, Vec<Result<(), TrySendError<u32>>>, u64, u64) {
+        let (sender, receiver) = OverflowChannel::new(2, overflow).into_inner();
+        let mut receiver = receiver.unwrap();
+
+        let mut results = Vec::new();
+        for value in 1..=3 {
+            results.push(sender.send(value).await);
+        }
+        let (dropped, spilled) = (sender.dropped(), sender.spilled());
+        drop(sender);
+
+        let mut received = Vec::new();
+        while let Some(value) = receiver.recv().await {
+            received.push(value);
+        }
+        (received, results, dropped, spilled)
+    }
+
+    #[tokio::test]
+    async fn overflow_policies() {
+        assert_eq!(drain(Overflow::DropNewest).await, (vec![1, 2], vec![Ok(()); 3], 1, 0));
+        assert_eq!(drain(Overflow::DropOldest).await, (vec![2, 3], vec![Ok(()); 3], 1, 0));
+        assert_eq!(drain(Overflow::Spill).await, (vec![1, 2, 3], vec![Ok(()); 3], 0, 1));
+        assert_eq!(
+            drain(Overflow::Fail).await,
+            (vec![1, 2], vec![Ok(()), Ok(()), Err(TrySendError::Full(3))], 0, 0)
+        );
+
+        let ctx = Context::<i32>::new();
+        let mut tick_in = ctx.receiver::<Tick>();
+        for value in 1..=3 {
+            ctx.sender::<Tick>().try_send(Tick(value)).unwrap();
+        }
+        assert_eq!(ctx.extract_channel::<Tick>().unwrap().dropped(), 1);
+        assert_eq!(tick_in.recv().await, Some(Tick(2)));
+    }
+
+    #[tokio::test]
+    async fn receive_until_closed() {
+        let (sender, receiver) = OverflowChannel::new(2, Overflow::Block).into_inner();
+        let mut receiver = receiver.unwrap();
+
+        sender.send(1).await.unwrap();
+        receiver.close();
+        assert_eq!(sender.send(2).await, Err(TrySendError::Closed(2)));
+
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, None);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    async fn reopen_for_held_senders() {
+        let ctx = Context::<i32>::new();
+        let tick_out = ctx.sender::<Tick>();
+        drop(ctx.receiver::<Tick>());
+        assert!(tick_out.is_closed());
+
+        let mut tick_in = ctx.receiver::<Tick>();
+        assert!(!tick_out.is_closed());
+        tick_out.send(Tick(1)).await.unwrap();
+        assert_eq!(tick_in.recv().await, Some(Tick(1)));
+        assert!(ctx.system().validate().receivers_taken_again.is_empty());
+    }
+}
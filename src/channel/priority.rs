@@ -0,0 +1,365 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::{SendError, TryRecvError, TrySendError};
+
+use crate::channel::overflow::Overflow;
+use crate::channel::queue::{self, Buffer, Shared};
+use crate::system::config::default_capacity;
+use crate::{Channel, ChannelCounters, ChannelKind};
+
+/// Messages with a greater priority are received first. Messages with equal priorities keep the FIFO order.
+/// The priority is applied by the channels created with [`PriorityChannel::new`], e.g. in `Message::create_channel`.
+pub trait Prioritized {
+    fn priority(&self) -> u32 {
+        0
+    }
+}
+
+pub(crate) type PriorityFn<T> = Arc<dyn Fn(&T) -> u32 + Send + Sync>;
+
+struct Entry<T> {
+    priority: u32,
+    seq: u64,
+    value: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct Queue<T> {
+    heap: BinaryHeap<Entry<T>>,
+    next_seq: u64,
+    priority: PriorityFn<T>,
+}
+
+impl<T> Queue<T> {
+    fn new(priority: PriorityFn<T>) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+            priority,
+        }
+    }
+}
+
+impl<T> Buffer for Queue<T> {
+    type Item = T;
+
+    fn push(&mut self, value: T) {
+        let priority = (self.priority)(&value);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(Entry { priority, seq, value });
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Some(self.heap.pop()?.value)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+pub struct PrioritySender<T> {
+    inner: queue::Sender<Queue<T>>,
+}
+
+impl<T> PrioritySender<T> {
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.inner.send(value).await.map_err(|err| SendError(err.into_inner()))
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(value)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity().saturating_sub(self.inner.len())
+    }
+
+    pub fn max_capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+}
+
+impl<T> Clone for PrioritySender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct PriorityReceiver<T> {
+    inner: queue::Receiver<Queue<T>>,
+}
+
+impl<T> PriorityReceiver<T> {
+    fn new(shared: Arc<Shared<Queue<T>>>) -> Self {
+        Self {
+            inner: queue::Receiver::new(shared),
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<T> {
+        self.inner.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    /// Closes the receiving half, the messages already queued can still be received.
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Created by [`Channel::create`], it has no priority to apply and keeps the FIFO order, so the message is expected
+/// to supply its channel in `Message::create_channel` with [`Self::new`] or [`Self::with_priority`].
+pub struct PriorityChannel<T> {
+    shared: Arc<Shared<Queue<T>>>,
+    priority: PriorityFn<T>,
+    sender: PrioritySender<T>,
+    receiver: Mutex<Option<PriorityReceiver<T>>>,
+}
+
+impl<T: Prioritized + 'static> PriorityChannel<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_priority(capacity, T::priority)
+    }
+}
+
+impl<T> PriorityChannel<T> {
+    pub fn with_priority(capacity: usize, priority: impl Fn(&T) -> u32 + Send + Sync + 'static) -> Self {
+        Self::with_priority_fn(capacity, Arc::new(priority))
+    }
+
+    fn with_priority_fn(capacity: usize, priority: PriorityFn<T>) -> Self {
+        let shared = Shared::new(Queue::new(priority.clone()), capacity, Overflow::Block);
+        Self {
+            sender: PrioritySender {
+                inner: queue::Sender::new(shared.clone()),
+            },
+            receiver: Mutex::new(Some(PriorityReceiver::new(shared.clone()))),
+            priority,
+            shared,
+        }
+    }
+
+    pub fn into_inner(self) -> (PrioritySender<T>, Option<PriorityReceiver<T>>) {
+        let receiver = self.receiver.lock().take();
+        (self.sender, receiver)
+    }
+
+    pub(crate) fn priority_fn(&self) -> PriorityFn<T> {
+        self.priority.clone()
+    }
+}
+
+impl<T: Send + 'static> Channel for PriorityChannel<T> {
+    type Sender = PrioritySender<T>;
+    type Receiver = PriorityReceiver<T>;
+
+    const KIND: ChannelKind = ChannelKind::Priority;
+
+    fn create() -> Self {
+        Self::with_priority(default_capacity(Self::KIND, 1024), |_| 0)
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::with_priority_fn(capacity, self.priority_fn())
+    }
+
+    fn sender(&self) -> Self::Sender {
+        self.sender.clone()
+    }
+
+    fn receiver(&self) -> Self::Receiver {
+        self.try_receiver()
+            .unwrap_or_else(|| PriorityReceiver::new(Shared::new(Queue::new(self.priority_fn()), 1, Overflow::Block)))
+    }
+
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        self.receiver.lock().take()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+
+    // The senders share the queue with the channel, so they follow it once a new receiver is in place.
+    // A receiver closed by hand is still alive though, and the channel is replaced then
+    fn reopen(&self) -> bool {
+        let mut receiver = self.receiver.lock();
+        if self.shared.has_receivers() {
+            return false;
+        }
+        *receiver = Some(PriorityReceiver::new(self.shared.clone()));
+        self.shared.reopen();
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.shared.capacity())
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        Some(self.shared.len())
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        Some(self.shared.counters())
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        Some(self.receiver.lock().is_none())
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        Some(self.shared.sender_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::error::{SendError, TryRecvError, TrySendError};
+
+    use crate::channel::priority::{Prioritized, PriorityChannel};
+    use crate::{Context, Message};
+
+    #[derive(Debug, PartialEq)]
+    enum Command {
+        Bulk(u32),
+        Stop,
+    }
+
+    impl Prioritized for Command {
+        fn priority(&self) -> u32 {
+            match self {
+                Self::Bulk(_) => 0,
+                Self::Stop => 10,
+            }
+        }
+    }
+
+    impl Message for Command {
+        type Channel = PriorityChannel<Self>;
+
+        fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+            PriorityChannel::new(3)
+        }
+    }
+
+    #[tokio::test]
+    async fn receive_by_priority() {
+        let ctx = Context::<i32>::new();
+        let command_out = ctx.actor_sender::<Command>(1);
+        let mut command_in = ctx.actor_receiver::<Command>(1);
+
+        command_out.send(Command::Bulk(1)).await.unwrap();
+        command_out.send(Command::Bulk(2)).await.unwrap();
+        command_out.send(Command::Stop).await.unwrap();
+        assert_eq!(
+            command_out.try_send(Command::Bulk(3)),
+            Err(TrySendError::Full(Command::Bulk(3)))
+        );
+        assert_eq!(ctx.system().channels_of(&1)[0].queue_len, Some(3));
+
+        assert_eq!(command_in.recv().await, Some(Command::Stop));
+        assert_eq!(command_in.recv().await, Some(Command::Bulk(1)));
+        command_out.send(Command::Bulk(3)).await.unwrap();
+        assert_eq!(command_in.recv().await, Some(Command::Bulk(2)));
+        assert_eq!(command_in.recv().await, Some(Command::Bulk(3)));
+
+        assert_eq!(ctx.is_actor_channel_closed::<Command>(1), Some(false));
+        drop(command_in);
+        assert_eq!(ctx.is_actor_channel_closed::<Command>(1), Some(true));
+        assert!(command_out.is_closed());
+        assert_eq!(command_out.send(Command::Stop).await, Err(SendError(Command::Stop)));
+    }
+
+    #[tokio::test]
+    async fn receive_until_senders_dropped() {
+        let (sender, receiver) = PriorityChannel::with_priority(8, |value: &u32| *value).into_inner();
+        let mut receiver = receiver.unwrap();
+
+        for value in [1, 3, 2] {
+            sender.clone().send(value).await.unwrap();
+        }
+        drop(sender);
+
+        assert_eq!(receiver.len(), 3);
+        assert_eq!(receiver.recv().await, Some(3));
+        assert_eq!(receiver.recv().await, Some(2));
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn receive_until_closed() {
+        let (sender, receiver) = PriorityChannel::with_priority(8, |value: &u32| *value).into_inner();
+        let mut receiver = receiver.unwrap();
+
+        sender.send(1).await.unwrap();
+        receiver.close();
+        assert_eq!(sender.send(2).await, Err(SendError(2)));
+
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, None);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    async fn reopen_for_held_senders() {
+        let ctx = Context::<i32>::new();
+        let command_out = ctx.actor_sender::<Command>(1);
+        drop(ctx.actor_receiver::<Command>(1));
+        assert!(command_out.is_closed());
+
+        let mut command_in = ctx.actor_receiver::<Command>(1);
+        command_out.send(Command::Bulk(1)).await.unwrap();
+        command_out.send(Command::Stop).await.unwrap();
+        assert_eq!(command_in.recv().await, Some(Command::Stop));
+        assert_eq!(command_in.recv().await, Some(Command::Bulk(1)));
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+use tokio::sync::Notify;
+
+use crate::channel::overflow::Overflow;
+use crate::{trace, ChannelCounters};
+
+/// The storage of a bounded queue, which decides the order the messages are received in.
+pub(crate) trait Buffer {
+    type Item;
+
+    fn push(&mut self, value: Self::Item);
+
+    fn pop(&mut self) -> Option<Self::Item>;
+
+    fn len(&self) -> usize;
+
+    fn clear(&mut self);
+}
+
+impl<T> Buffer for VecDeque<T> {
+    type Item = T;
+
+    fn push(&mut self, value: T) {
+        self.push_back(value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn clear(&mut self) {
+        VecDeque::clear(self);
+    }
+}
+
+/// The core of the bounded channels: the queue, its capacity and the halves attached to it.
+pub(crate) struct Shared<B> {
+    buffer: Mutex<B>,
+    capacity: usize,
+    overflow: Overflow,
+    items: Notify,
+    space: Notify,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+    spilled: AtomicU64,
+    counters: ChannelCounters,
+}
+
+impl<B: Buffer> Shared<B> {
+    pub(crate) fn new(buffer: B, capacity: usize, overflow: Overflow) -> Arc<Self> {
+        Arc::new(Self {
+            buffer: Mutex::new(buffer),
+            capacity: capacity.max(1),
+            overflow,
+            items: Notify::new(),
+            space: Notify::new(),
+            senders: AtomicUsize::new(0),
+            receivers: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+            spilled: AtomicU64::new(0),
+            counters: Default::default(),
+        })
+    }
+
+    // The policies other than `Block` and `Fail` drop or spill in the order of the buffer
+    fn push(&self, value: B::Item) -> Result<(), TrySendError<B::Item>> {
+        if self.is_closed() {
+            return Err(TrySendError::Closed(value));
+        }
+
+        let mut buffer = self.buffer.lock();
+        if buffer.len() >= self.capacity {
+            match self.overflow {
+                Overflow::Block | Overflow::Fail => return Err(TrySendError::Full(value)),
+                Overflow::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                },
+                Overflow::DropOldest => {
+                    buffer.pop();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                },
+                Overflow::Spill => {
+                    self.spilled.fetch_add(1, Ordering::Relaxed);
+                },
+            }
+        }
+        buffer.push(value);
+        drop(buffer);
+
+        self.items.notify_one();
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<B::Item> {
+        let value = self.buffer.lock().pop()?;
+        self.space.notify_one();
+        self.counters.record_received();
+        trace::received::<B::Item>();
+        Some(value)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.senders.load(Ordering::Acquire) == 0 || self.is_closed()
+    }
+
+    /// Sends the message according to the overflow policy, waiting for space only with [`Overflow::Block`].
+    pub(crate) async fn send(&self, mut value: B::Item) -> Result<(), TrySendError<B::Item>> {
+        let _blocking = self.counters.start_blocking();
+        let result = loop {
+            let mut space = pin!(self.space.notified());
+            space.as_mut().enable();
+
+            match self.push(value) {
+                Err(TrySendError::Full(rejected)) if self.overflow == Overflow::Block => {
+                    value = rejected;
+                    space.await;
+                },
+                result => break result,
+            }
+        };
+        self.counters.record(&result);
+        trace::sent::<B::Item, _, _>(&result);
+        result
+    }
+
+    pub(crate) fn try_send(&self, value: B::Item) -> Result<(), TrySendError<B::Item>> {
+        let result = self.push(value);
+        self.counters.record(&result);
+        trace::sent::<B::Item, _, _>(&result);
+        result
+    }
+
+    pub(crate) async fn recv(&self) -> Option<B::Item> {
+        loop {
+            let mut items = pin!(self.items.notified());
+            items.as_mut().enable();
+
+            // Checked first, so the messages sent before the last sender is dropped are still popped
+            let disconnected = self.is_disconnected();
+            if let Some(value) = self.pop() {
+                return Some(value);
+            }
+            if disconnected {
+                return None;
+            }
+            items.await;
+        }
+    }
+
+    pub(crate) fn try_recv(&self) -> Result<B::Item, TryRecvError> {
+        let disconnected = self.is_disconnected();
+        match self.pop() {
+            Some(value) => Ok(value),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Rejects the messages sent from now on, the queued ones can still be received.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.space.notify_waiters();
+    }
+
+    pub(crate) fn reopen(&self) {
+        self.closed.store(false, Ordering::Release);
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buffer.lock().len()
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn spilled(&self) -> u64 {
+        self.spilled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn counters(&self) -> &ChannelCounters {
+        &self.counters
+    }
+
+    pub(crate) fn has_receivers(&self) -> bool {
+        self.receivers.load(Ordering::Acquire) > 0
+    }
+
+    // The channel keeps a sender of its own
+    pub(crate) fn sender_count(&self) -> usize {
+        self.senders.load(Ordering::Acquire).saturating_sub(1)
+    }
+}
+
+/// A sending half. The receivers are woken up to disconnect when the last one drops.
+pub(crate) struct Sender<B: Buffer> {
+    shared: Arc<Shared<B>>,
+}
+
+impl<B: Buffer> Sender<B> {
+    pub(crate) fn new(shared: Arc<Shared<B>>) -> Self {
+        shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self { shared }
+    }
+}
+
+impl<B: Buffer> Deref for Sender<B> {
+    type Target = Shared<B>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.shared
+    }
+}
+
+impl<B: Buffer> Clone for Sender<B> {
+    fn clone(&self) -> Self {
+        Self::new(self.shared.clone())
+    }
+}
+
+impl<B: Buffer> Drop for Sender<B> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.items.notify_waiters();
+        }
+    }
+}
+
+/// A receiving half. The channel is closed when the last one drops, and the queued messages are dropped, as they
+/// are with the receiver of a tokio channel.
+pub(crate) struct Receiver<B: Buffer> {
+    shared: Arc<Shared<B>>,
+}
+
+impl<B: Buffer> Receiver<B> {
+    pub(crate) fn new(shared: Arc<Shared<B>>) -> Self {
+        shared.receivers.fetch_add(1, Ordering::AcqRel);
+        Self { shared }
+    }
+}
+
+impl<B: Buffer> Deref for Receiver<B> {
+    type Target = Shared<B>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.shared
+    }
+}
+
+impl<B: Buffer> Clone for Receiver<B> {
+    fn clone(&self) -> Self {
+        Self::new(self.shared.clone())
+    }
+}
+
+impl<B: Buffer> Drop for Receiver<B> {
+    fn drop(&mut self) {
+        if self.shared.receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.close();
+            self.shared.buffer.lock().clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::error::TrySendError;
+    use tokio::time;
+
+    use crate::channel::overflow::Overflow;
+    use crate::channel::queue::{Receiver, Sender, Shared};
+
+    #[tokio::test]
+    async fn block_until_received() {
+        let shared = Shared::new(VecDeque::new(), 1, Overflow::Block);
+        let (sender, receiver) = (Sender::new(shared.clone()), Receiver::new(shared));
+
+        sender.send(1).await.unwrap();
+        assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send(2).await }
+        });
+        time::sleep(Duration::from_millis(5)).await;
+        assert!(!blocked.is_finished());
+
+        assert_eq!(receiver.recv().await, Some(1));
+        blocked.await.unwrap().unwrap();
+        drop(sender);
+        assert_eq!(receiver.clone().recv().await, Some(2));
+        assert_eq!(receiver.recv().await, None);
+    }
+}
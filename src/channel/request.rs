@@ -0,0 +1,235 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time;
+
+use crate::system::config::default_capacity;
+use crate::{Channel, ChannelCounters, ChannelKind, MpscChannel, MpscReceiver, MpscSender};
+
+pub trait Request: Send + 'static {
+    type Response: Send + 'static;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AskError {
+    Closed,
+    NoResponse,
+    Timeout,
+}
+
+impl fmt::Display for AskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "request channel is closed"),
+            Self::NoResponse => write!(f, "responder was dropped without answering"),
+            Self::Timeout => write!(f, "request timed out"),
+        }
+    }
+}
+
+impl Error for AskError {}
+
+pub struct Responder<R> {
+    sender: oneshot::Sender<R>,
+}
+
+impl<R> Responder<R> {
+    pub fn respond(self, response: R) -> Result<(), R> {
+        self.sender.send(response)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+pub type Asked<M> = (M, Responder<<M as Request>::Response>);
+
+pub struct RequestSender<M: Request> {
+    sender: MpscSender<Asked<M>>,
+}
+
+impl<M: Request> Clone for RequestSender<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<M: Request> RequestSender<M> {
+    pub async fn ask(&self, request: M) -> Result<M::Response, AskError> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send((request, Responder { sender }))
+            .await
+            .map_err(|_| AskError::Closed)?;
+        receiver.await.map_err(|_| AskError::NoResponse)
+    }
+
+    pub async fn ask_timeout(&self, request: M, timeout: Duration) -> Result<M::Response, AskError> {
+        time::timeout(timeout, self.ask(request))
+            .await
+            .unwrap_or(Err(AskError::Timeout))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+pub struct RequestReceiver<M: Request> {
+    receiver: MpscReceiver<Asked<M>>,
+}
+
+impl<M: Request> RequestReceiver<M> {
+    pub async fn recv(&mut self) -> Option<Asked<M>> {
+        self.receiver.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Option<Asked<M>> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn close(&mut self) {
+        self.receiver.close()
+    }
+}
+
+pub struct RequestChannel<M: Request> {
+    inner: MpscChannel<Asked<M>>,
+}
+
+impl<M: Request> RequestChannel<M> {
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            inner: MpscChannel::new(buffer),
+        }
+    }
+}
+
+impl<M: Request> Channel for RequestChannel<M> {
+    type Sender = RequestSender<M>;
+    type Receiver = RequestReceiver<M>;
+
+    const KIND: ChannelKind = ChannelKind::Request;
+
+    fn create() -> Self {
+        Self::new(default_capacity(Self::KIND, 1024))
+    }
+
+    fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity)
+    }
+
+    fn sender(&self) -> Self::Sender {
+        RequestSender {
+            sender: self.inner.sender(),
+        }
+    }
+
+    fn receiver(&self) -> Self::Receiver {
+        RequestReceiver {
+            receiver: self.inner.receiver(),
+        }
+    }
+
+    fn try_receiver(&self) -> Option<Self::Receiver> {
+        self.inner.try_receiver().map(|receiver| RequestReceiver { receiver })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn reopen(&self) -> bool {
+        self.inner.reopen()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    fn queue_len(&self) -> Option<usize> {
+        self.inner.queue_len()
+    }
+
+    fn counters(&self) -> Option<&ChannelCounters> {
+        self.inner.counters()
+    }
+
+    fn is_receiver_taken(&self) -> Option<bool> {
+        self.inner.is_receiver_taken()
+    }
+
+    fn sender_count(&self) -> Option<usize> {
+        self.inner.sender_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::channel::request::{AskError, Request, RequestChannel};
+    use crate::{Context, Message};
+
+    struct Double(u32);
+
+    impl Message for Double {
+        type Channel = RequestChannel<Self>;
+    }
+
+    impl Request for Double {
+        type Response = u32;
+    }
+
+    #[tokio::test]
+    async fn ask_actor() {
+        let ctx = Context::<i32>::new();
+        let mut double_in = ctx.actor_receiver::<Double>(1);
+
+        let actor_ctx = ctx.clone();
+        crate::spawn_event_loop!(actor_ctx, {
+            Some((Double(value), responder)) = double_in.recv() => {
+                if value > 0 {
+                    responder.respond(value * 2).ok();
+                }
+            },
+        });
+
+        assert_eq!(ctx.ask::<Double>(1, Double(21)).await, Ok(42));
+        assert_eq!(ctx.ask::<Double>(1, Double(0)).await, Err(AskError::NoResponse));
+
+        let double_out = ctx.actor_sender::<Double>(1);
+        ctx.shutdown().await;
+        assert_eq!(double_out.ask(Double(1)).await, Err(AskError::Closed));
+    }
+
+    #[tokio::test]
+    async fn ask_timeout() {
+        let ctx = Context::<i32>::new();
+        let _double_in = ctx.actor_receiver::<Double>(1);
+
+        assert_eq!(
+            ctx.ask_timeout::<Double>(1, Double(1), Duration::from_millis(10)).await,
+            Err(AskError::Timeout)
+        );
+    }
+
+    #[tokio::test]
+    async fn ask_after_receiver_dropped() {
+        let ctx = Context::<i32>::new();
+        drop(ctx.actor_receiver::<Double>(1));
+
+        assert_eq!(ctx.ask::<Double>(1, Double(1)).await, Err(AskError::Closed));
+        assert_eq!(
+            ctx.ask_timeout::<Double>(1, Double(1), Duration::from_secs(10)).await,
+            Err(AskError::Closed)
+        );
+        assert_eq!(ctx.ask::<Double>(2, Double(1)).await, Err(AskError::Closed));
+        assert!(ctx.system().channels_of(&2).is_empty());
+    }
+}
@@ -1,36 +1,46 @@
 use std::sync::Arc;
 
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::watch;
 
-use crate::Channel;
+use crate::{Channel, ChannelKind};
 
 pub struct WatchChannel<T> {
     sender: Arc<Mutex<Option<watch::Sender<Option<T>>>>>,
-    receiver: Arc<Mutex<watch::Receiver<Option<T>>>>,
+    receiver: Arc<RwLock<watch::Receiver<Option<T>>>>,
 }
 
 impl<T: Send + Sync> Channel for WatchChannel<T> {
     type Sender = watch::Sender<Option<T>>;
     type Receiver = watch::Receiver<Option<T>>;
 
+    const KIND: ChannelKind = ChannelKind::Watch;
+
     fn create() -> Self {
         let (sender, receiver) = watch::channel(None);
         Self {
             sender: Arc::new(Mutex::new(Some(sender))),
-            receiver: Arc::new(Mutex::new(receiver)),
+            receiver: Arc::new(RwLock::new(receiver)),
         }
     }
 
     fn sender(&self) -> Self::Sender {
-        self.sender.lock().take().unwrap_or_else(|| watch::channel(None).0)
+        self.try_sender().unwrap_or_else(|| watch::channel(None).0)
+    }
+
+    fn try_sender(&self) -> Option<Self::Sender> {
+        self.sender.lock().take()
     }
 
     fn receiver(&self) -> Self::Receiver {
-        self.receiver.lock().clone()
+        self.receiver.read().clone()
     }
 
     fn is_closed(&self) -> bool {
-        self.receiver.lock().has_changed().is_err()
+        self.receiver.read().has_changed().is_err()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(1)
     }
 }
@@ -1,67 +1,122 @@
+use std::any;
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 use std::future::Future;
 use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use futures::future;
-use parking_lot::{Mutex, MutexGuard};
-use tokio::task::{JoinError, JoinHandle};
+use futures::FutureExt;
+use tokio::task::JoinError;
+use tokio::time;
 
-use crate::{Channel, Message, System, TaskId};
+use crate::actor::{self, Actor, ActorHandle};
+use crate::actor_ref::ActorRef;
+use crate::system::{ActorStop, ShutdownPhase, SystemShutdown};
+use crate::task::{self, CancellationToken, TaskError, TaskFailure, TaskInfo, TaskMeta, TaskOutcome, TaskState};
+pub use crate::task::{ShutdownReport, TaskHandles};
+use crate::trace;
+use crate::{AskError, Channel, Channels, Error, Message, Receiver, Request, RequestChannel, System, TaskId};
 
-#[derive(Clone, Default)]
-pub struct TaskHandles {
-    handles: Arc<Mutex<HashMap<TaskId, JoinHandle<()>>>>,
+pub type DefaultActorId = String;
+pub type DefaultContext = Context<DefaultActorId>;
+
+pub struct Context<ActorId = DefaultActorId> {
+    system: Arc<System<ActorId>>,
+    handles: TaskHandles,
+    phase: ShutdownPhase,
 }
 
-impl TaskHandles {
-    pub fn add(&self, id: TaskId, handle: JoinHandle<()>) {
-        self.handles.lock().insert(id, handle);
+impl<ActorId> Clone for Context<ActorId> {
+    fn clone(&self) -> Self {
+        Self {
+            system: self.system.clone(),
+            handles: self.handles.clone(),
+            phase: self.phase,
+        }
     }
+}
 
-    pub fn remove(&self, id: &TaskId) -> Option<JoinHandle<()>> {
-        self.handles.lock().remove(id)
+impl<ActorId> Default for Context<ActorId> {
+    fn default() -> Self {
+        Self {
+            system: Default::default(),
+            handles: Default::default(),
+            phase: Default::default(),
+        }
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.handles.lock().is_empty()
-    }
+pub struct TaskBuilder<'a, ActorId> {
+    ctx: &'a Context<ActorId>,
+    meta: TaskMeta,
+}
 
-    pub fn len(&self) -> usize {
-        self.handles.lock().len()
+impl<ActorId> TaskBuilder<'_, ActorId> {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.meta.name = Some(name.into());
+        self
     }
 
-    pub async fn join(&self, id: &TaskId) -> Result<(), JoinError> {
-        if let Some(handle) = self.remove(id) {
-            handle.await
-        } else {
-            Ok(())
-        }
+    pub fn spawn<T>(self, future: T) -> TaskId
+    where
+        T: Future<Output = ()> + Send + 'static,
+    {
+        self.try_spawn(async move {
+            future.await;
+            Ok::<_, TaskError>(())
+        })
     }
 
-    pub async fn join_all(&self) {
-        let handles: Vec<_> = self.handles.lock().drain().map(|(_, handle)| handle).collect();
-        future::join_all(handles).await;
+    pub fn try_spawn<T, E>(self, future: T) -> TaskId
+    where
+        T: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<TaskError>,
+    {
+        let (task_id, hook) = {
+            let system = self.ctx.system();
+            (system.next_task_id(), system.task_hook_slot())
+        };
+        let handles = self.ctx.handles.clone();
+        let token = CancellationToken::new();
+        let future = task::scope(task_id, token.clone(), future);
+        let report = move |failure: TaskFailure<'_>| {
+            trace::task_failed(&failure);
+            let hook = hook.read().clone();
+            if let Some(hook) = hook {
+                hook(task_id, failure);
+            }
+        };
+        let future = trace::instrument(task_id, &self.meta, async move {
+            match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(Ok(())) => {
+                    handles.complete(task_id, TaskState::Finished);
+                    Ok(())
+                },
+                Ok(Err(err)) => {
+                    let err = err.into();
+                    report(TaskFailure::Error(&*err));
+                    handles.complete(task_id, TaskState::Failed);
+                    Err(err)
+                },
+                Err(payload) => {
+                    report(TaskFailure::Panic(&*payload));
+                    handles.complete(task_id, TaskState::Failed);
+                    panic::resume_unwind(payload)
+                },
+            }
+        });
+        self.ctx.handles.spawn(task_id, self.meta, token, future);
+        task_id
     }
 }
 
-pub type DefaultActorId = String;
-pub type DefaultContext = Context<DefaultActorId>;
-
-#[derive(Clone)]
-pub struct Context<ActorId = DefaultActorId> {
-    system: Arc<Mutex<System<ActorId>>>,
-    handles: TaskHandles,
-}
-
-impl<ActorId> Default for Context<ActorId> {
-    fn default() -> Self {
-        Self {
-            system: Default::default(),
-            handles: Default::default(),
-        }
+impl<ActorId: fmt::Display> TaskBuilder<'_, ActorId> {
+    pub fn actor(mut self, actor_id: &ActorId) -> Self {
+        self.meta.actor_id = Some(actor_id.to_string());
+        self
     }
 }
 
@@ -72,8 +127,27 @@ impl<ActorId> Context<ActorId> {
 
     pub fn from_system(system: System<ActorId>) -> Self {
         Self {
-            system: Arc::new(Mutex::new(system)),
+            system: Arc::new(system),
             handles: Default::default(),
+            phase: Default::default(),
+        }
+    }
+
+    pub fn with_shutdown_phase(&self, phase: ShutdownPhase) -> Self {
+        Self { phase, ..self.clone() }
+    }
+
+    pub fn shutdown_phase(&self) -> ShutdownPhase {
+        self.phase
+    }
+
+    pub fn task(&self) -> TaskBuilder<'_, ActorId> {
+        TaskBuilder {
+            ctx: self,
+            meta: TaskMeta {
+                phase: self.phase,
+                ..Default::default()
+            },
         }
     }
 
@@ -81,14 +155,27 @@ impl<ActorId> Context<ActorId> {
     where
         T: Future<Output = ()> + Send + 'static,
     {
-        let task_id = self.system().next_task_id();
-        let handles = self.handles.clone();
-        let handle = tokio::spawn(async move {
-            future.await;
-            handles.remove(&task_id);
-        });
-        self.handles.add(task_id, handle);
-        task_id
+        self.task().spawn(future)
+    }
+
+    pub fn try_spawn<T, E>(&self, future: T) -> TaskId
+    where
+        T: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<TaskError>,
+    {
+        self.task().try_spawn(future)
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.handles.tasks()
+    }
+
+    pub fn cancel(&self, id: &TaskId) -> bool {
+        self.handles.cancel(id)
+    }
+
+    pub fn abort(&self, id: &TaskId) -> bool {
+        self.handles.abort(id)
     }
 
     pub fn extract_channel<M: Message>(&self) -> Option<M::Channel> {
@@ -121,17 +208,58 @@ impl<ActorId> Context<ActorId> {
         self.system().receiver::<M>()
     }
 
+    pub fn try_sender<M: Message>(&self) -> Result<<M::Channel as Channel>::Sender, Error> {
+        self.system().try_sender::<M>()
+    }
+
+    pub fn try_receiver<M: Message>(&self) -> Result<<M::Channel as Channel>::Receiver, Error> {
+        self.system().try_receiver::<M>()
+    }
+
     pub fn is_channel_closed<M: Message>(&self) -> Option<bool> {
         self.system().get_channel::<M>().map(|channel| channel.is_closed())
     }
 
-    pub fn system(&self) -> MutexGuard<'_, System<ActorId>> {
-        self.system.lock()
+    pub fn shutdown_receiver(&self) -> Receiver<SystemShutdown> {
+        self.system().shutdown_receiver(self.phase)
+    }
+
+    pub fn is_shutdown_channel_closed(&self) -> Option<bool> {
+        self.system().is_shutdown_channel_closed(self.phase)
+    }
+
+    pub fn system(&self) -> &System<ActorId> {
+        &self.system
     }
 
     pub async fn shutdown(&self) {
-        self.system().shutdown();
-        self.join_all().await
+        let started_at = Instant::now();
+        for phase in self.shutdown_phases() {
+            self.system().shutdown_phase(phase);
+            self.handles.join_phase(phase).await;
+        }
+        self.join_all().await;
+        self.system().runtime_counters().record_shutdown(started_at.elapsed());
+    }
+
+    /// Shuts down the phases in order, all of them together within the timeout.
+    pub async fn shutdown_with_timeout(&self, timeout: Duration) -> ShutdownReport {
+        let started_at = Instant::now();
+        let deadline = time::Instant::now() + timeout;
+        let mut report = ShutdownReport::default();
+        for phase in self.shutdown_phases() {
+            self.system().shutdown_phase(phase);
+            report.merge(self.handles.join_phase_until(phase, deadline).await);
+        }
+        report.merge(self.handles.join_all_until(deadline).await);
+        self.system().runtime_counters().record_shutdown(started_at.elapsed());
+        report
+    }
+
+    fn shutdown_phases(&self) -> BTreeSet<ShutdownPhase> {
+        let mut phases = self.handles.phases();
+        phases.extend(self.system().shutdown_phases());
+        phases
     }
 
     pub async fn join_all(&self) {
@@ -142,6 +270,14 @@ impl<ActorId> Context<ActorId> {
         self.handles.join(id).await
     }
 
+    pub async fn join_outcome(&self, id: &TaskId) -> Option<TaskOutcome> {
+        self.handles.join_outcome(id).await
+    }
+
+    pub async fn join_all_outcomes(&self) -> Vec<(TaskId, TaskOutcome)> {
+        self.handles.join_all_outcomes().await
+    }
+
     pub fn handles(&self) -> &TaskHandles {
         &self.handles
     }
@@ -157,6 +293,89 @@ impl<ActorId: Eq + Hash> Context<ActorId> {
             .get_actor_channel::<M>(actor_id)
             .map(|channel| channel.sender())
     }
+}
+
+impl<ActorId: Eq + Hash + fmt::Display> Context<ActorId> {
+    pub fn actor_sender<M: Message>(&self, actor_id: impl Into<ActorId>) -> <M::Channel as Channel>::Sender {
+        self.system().actor_sender::<M>(actor_id.into())
+    }
+
+    pub fn actor_receiver<M: Message>(&self, actor_id: impl Into<ActorId>) -> <M::Channel as Channel>::Receiver {
+        self.system().actor_receiver::<M>(actor_id.into())
+    }
+
+    pub fn try_actor_sender<M: Message>(
+        &self,
+        actor_id: impl Into<ActorId>,
+    ) -> Result<<M::Channel as Channel>::Sender, Error> {
+        self.system().try_actor_sender::<M>(actor_id.into())
+    }
+
+    pub fn try_actor_receiver<M: Message>(
+        &self,
+        actor_id: impl Into<ActorId>,
+    ) -> Result<<M::Channel as Channel>::Receiver, Error> {
+        self.system().try_actor_receiver::<M>(actor_id.into())
+    }
+
+    pub fn actor_ref<M: Message>(&self, actor_id: impl Into<ActorId>) -> ActorRef<M, ActorId> {
+        ActorRef::new(self.clone(), actor_id.into())
+    }
+
+    pub fn stop_actor(&self, actor_id: impl Into<ActorId>) {
+        self.system().stop_actor(actor_id.into())
+    }
+
+    pub fn actor_stop_receiver(&self, actor_id: impl Into<ActorId>) -> Receiver<ActorStop> {
+        self.actor_receiver::<ActorStop>(actor_id)
+    }
+
+    pub fn extract_actor(&self, actor_id: impl Borrow<ActorId>) -> Option<Channels> {
+        self.system().extract_actor(actor_id.borrow())
+    }
+
+    /// Removes the actors whose channels are all closed and which have no running tasks bound to them.
+    pub fn prune_closed(&self) -> Vec<ActorId> {
+        let busy: HashSet<_> = self
+            .tasks()
+            .into_iter()
+            .filter(|task| task.state == TaskState::Running)
+            .filter_map(|task| task.actor_id)
+            .collect();
+        self.system()
+            .prune_closed_if(|actor_id| !busy.contains(&actor_id.to_string()))
+    }
+
+    pub fn is_actor_channel_closed<M: Message>(&self, actor_id: impl Borrow<ActorId>) -> Option<bool> {
+        self.system()
+            .get_actor_channel::<M>(actor_id.borrow())
+            .map(|channel| channel.is_closed())
+    }
+
+    pub fn ask<M>(
+        &self,
+        actor_id: impl Into<ActorId>,
+        request: M,
+    ) -> impl Future<Output = Result<M::Response, AskError>>
+    where
+        M: Request + Message<Channel = RequestChannel<M>>,
+    {
+        let sender = self.get_actor_sender::<M>(&actor_id.into());
+        async move { sender.ok_or(AskError::Closed)?.ask(request).await }
+    }
+
+    pub fn ask_timeout<M>(
+        &self,
+        actor_id: impl Into<ActorId>,
+        request: M,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<M::Response, AskError>>
+    where
+        M: Request + Message<Channel = RequestChannel<M>>,
+    {
+        let sender = self.get_actor_sender::<M>(&actor_id.into());
+        async move { sender.ok_or(AskError::Closed)?.ask_timeout(request, timeout).await }
+    }
 
     pub fn actor_sender_of_custom_channel<M: Message>(
         &self,
@@ -176,19 +395,17 @@ impl<ActorId: Eq + Hash> Context<ActorId> {
     }
 }
 
-impl<ActorId: Eq + Hash + fmt::Display> Context<ActorId> {
-    pub fn actor_sender<M: Message>(&self, actor_id: impl Into<ActorId>) -> <M::Channel as Channel>::Sender {
-        self.system().actor_sender::<M>(actor_id.into())
-    }
-
-    pub fn actor_receiver<M: Message>(&self, actor_id: impl Into<ActorId>) -> <M::Channel as Channel>::Receiver {
-        self.system().actor_receiver::<M>(actor_id.into())
-    }
-
-    pub fn is_actor_channel_closed<M: Message>(&self, actor_id: impl Borrow<ActorId>) -> Option<bool> {
-        self.system()
-            .get_actor_channel::<M>(actor_id.borrow())
-            .map(|channel| channel.is_closed())
+impl<ActorId: Eq + Hash + fmt::Display + Clone + Send + Sync + 'static> Context<ActorId> {
+    pub fn start_actor<A: Actor<Id = ActorId>>(&self, actor_id: impl Into<ActorId>, actor: A) -> ActorHandle<A> {
+        let actor_id = actor_id.into();
+        let inbox = A::inbox(self, &actor_id);
+        let stop_in = self.actor_stop_receiver(actor_id.clone());
+        let task_id = self
+            .task()
+            .name(any::type_name::<A>())
+            .actor(&actor_id)
+            .spawn(actor::run(self.clone(), stop_in, actor, inbox));
+        ActorHandle::new(actor_id, task_id, self.clone())
     }
 }
 
@@ -200,9 +417,17 @@ impl<ActorId> From<System<ActorId>> for Context<ActorId> {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::future;
+    use parking_lot::Mutex;
     use tokio::sync::mpsc::error::SendError;
 
-    use crate::{Context, Message, MpscChannel};
+    use crate::{
+        Channel, Context, Error, Freeze, Message, MpscChannel, ShutdownPhase, System, TaskOutcome, TaskState,
+        WatchChannel,
+    };
 
     struct Value(&'static str);
 
@@ -268,4 +493,249 @@ mod tests {
         drop(actor_receiver);
         assert!(ctx.is_actor_channel_closed::<Value>(1_i32).unwrap_or(true));
     }
+
+    #[tokio::test]
+    async fn shutdown_with_timeout() {
+        let ctx = Context::<i32>::new();
+
+        let mut value_in = ctx.receiver::<Value>();
+        let cooperative_ctx = ctx.clone();
+        let cooperative = crate::spawn_event_loop!(cooperative_ctx, {
+            Some(_) = value_in.recv() => {},
+        });
+        let stuck = ctx.spawn(future::pending());
+        let panicked = ctx.spawn(async { panic!("task failure") });
+        tokio::task::yield_now().await;
+
+        let report = ctx.shutdown_with_timeout(Duration::from_millis(10)).await;
+        assert_eq!(report.finished, vec![cooperative]);
+        assert_eq!(report.aborted, vec![stuck]);
+        assert_eq!(report.panicked, vec![panicked]);
+        assert!(!report.is_clean());
+        assert!(ctx.handles().is_empty());
+    }
+
+    #[tokio::test]
+    async fn shutdown_phases_within_timeout() {
+        let ctx = Context::<i32>::new();
+        let stuck: Vec<_> = (0..3)
+            .map(|phase| ctx.with_shutdown_phase(ShutdownPhase(phase)).spawn(future::pending()))
+            .collect();
+        tokio::task::yield_now().await;
+
+        let started_at = std::time::Instant::now();
+        let report = ctx.shutdown_with_timeout(Duration::from_millis(50)).await;
+        assert!(started_at.elapsed() < Duration::from_millis(140));
+        assert_eq!(report.aborted, stuck);
+    }
+
+    #[tokio::test]
+    async fn phased_shutdown() {
+        let ctx = Context::<i32>::new();
+        let stopped = Arc::new(Mutex::new(Vec::new()));
+
+        for (phase, name) in [(2, "storage"), (0, "http"), (1, "writer")] {
+            let ctx = ctx.with_shutdown_phase(ShutdownPhase(phase));
+            let stopped = stopped.clone();
+            ctx.clone().spawn(async move {
+                crate::event_loop!(ctx, {
+                    _ = future::pending::<()>() => {},
+                });
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                stopped.lock().push(name);
+            });
+        }
+        tokio::task::yield_now().await;
+
+        ctx.shutdown().await;
+        assert_eq!(*stopped.lock(), ["http", "writer", "storage"]);
+    }
+
+    #[tokio::test]
+    async fn task_outcomes() {
+        let ctx = Context::<i32>::new();
+        let failures = Arc::new(Mutex::new(Vec::new()));
+
+        let hook_failures = failures.clone();
+        ctx.system()
+            .set_task_hook(move |id, failure| hook_failures.lock().push((id, failure.message())));
+
+        let finished = ctx.try_spawn(async { Ok::<_, String>(()) });
+        let failed = ctx.try_spawn(async { Err("task error") });
+        let panicked = ctx.spawn(async { panic!("task panic") });
+        let cancelled = ctx.spawn(future::pending());
+        tokio::task::yield_now().await;
+        let cancelled = ctx.handles().remove(&cancelled).unwrap();
+        cancelled.abort();
+        assert!(matches!(TaskOutcome::from(cancelled.await), TaskOutcome::Cancelled));
+
+        let outcomes = ctx.join_all_outcomes().await;
+        assert_eq!(outcomes.len(), 3);
+        assert!(ctx.join_outcome(&finished).await.is_none());
+        assert!(matches!(&outcomes[0], (id, TaskOutcome::Finished) if *id == finished));
+        assert!(
+            matches!(&outcomes[1], (id, TaskOutcome::Failed(err)) if *id == failed && err.to_string() == "task error")
+        );
+        assert!(matches!(&outcomes[2], (id, TaskOutcome::Panicked(_)) if *id == panicked));
+        assert!(ctx.tasks().is_empty());
+        assert_eq!(
+            *failures.lock(),
+            [(failed, "task error".to_string()), (panicked, "task panic".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn hook_installed_after_spawn() {
+        let ctx = Context::<i32>::new();
+        let (fail_out, fail_in) = tokio::sync::oneshot::channel::<()>();
+        let failing = ctx.try_spawn(async move {
+            fail_in.await.ok();
+            Err("late failure")
+        });
+        tokio::task::yield_now().await;
+
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let hook_failures = failures.clone();
+        ctx.system()
+            .set_task_hook(move |id, failure| hook_failures.lock().push((id, failure.message())));
+        fail_out.send(()).unwrap();
+
+        assert!(matches!(ctx.join_outcome(&failing).await, Some(TaskOutcome::Failed(_))));
+        assert_eq!(*failures.lock(), [(failing, "late failure".to_string())]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn fast_tasks_are_finished() {
+        let ctx = Context::<i32>::new();
+        let ids: Vec<_> = (0..100).map(|_| ctx.spawn(async {})).collect();
+        while ctx.tasks().iter().any(|task| task.state == TaskState::Running) {
+            tokio::task::yield_now().await;
+        }
+
+        let tasks = ctx.tasks();
+        assert_eq!(tasks.iter().map(|task| task.id).collect::<Vec<_>>(), ids);
+        assert!(tasks.iter().all(|task| task.state == TaskState::Finished));
+    }
+
+    #[tokio::test]
+    async fn task_introspection() {
+        let ctx = Context::<i32>::new();
+
+        let running = ctx.task().name("running").actor(&7).spawn(future::pending());
+        let failed = ctx.task().name("failed").try_spawn(async { Err("failure") });
+        let finished = ctx.spawn(async {});
+        tokio::task::yield_now().await;
+
+        let tasks = ctx.tasks();
+        assert_eq!(tasks.len(), 3);
+
+        assert_eq!(tasks[0].id, running);
+        assert_eq!(tasks[0].name.as_deref(), Some("running"));
+        assert_eq!(tasks[0].actor_id.as_deref(), Some("7"));
+        assert_eq!(tasks[0].state, TaskState::Running);
+
+        assert_eq!(tasks[1].id, failed);
+        assert_eq!(tasks[1].name.as_deref(), Some("failed"));
+        assert_eq!(tasks[1].actor_id, None);
+        assert_eq!(tasks[1].state, TaskState::Failed);
+        assert!(tasks[0].spawned_at <= tasks[1].spawned_at);
+        assert_eq!(tasks[2].id, finished);
+        assert_eq!(tasks[2].state, TaskState::Finished);
+
+        ctx.join(&failed).await.unwrap();
+        assert_eq!(ctx.tasks().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn prune_closed_actors() {
+        let ctx = Context::<i32>::new();
+
+        drop(ctx.actor_receiver::<Value>(1));
+        let _value_in = ctx.actor_receiver::<Value>(2);
+        drop(ctx.actor_receiver::<Value>(3));
+        ctx.task().actor(&3).spawn(future::pending());
+
+        assert_eq!(ctx.prune_closed(), [1]);
+        let mut actor_ids = ctx.system().actor_ids();
+        actor_ids.sort();
+        assert_eq!(actor_ids, [2, 3]);
+
+        let channels = ctx.extract_actor(2).unwrap();
+        assert_eq!(channels.len(), 1);
+        assert!(!channels.get::<Value>().unwrap().is_closed());
+        assert!(ctx.extract_actor(2).is_none());
+
+        assert!(ctx.system().prune_closed_if(|_| false).is_empty());
+        assert_eq!(ctx.system().prune_closed(), [3]);
+    }
+
+    #[tokio::test]
+    async fn cancel_and_abort_task() {
+        let ctx = Context::<i32>::new();
+
+        let mut value_in = ctx.receiver::<Value>();
+        let loop_ctx = ctx.clone();
+        let cooperative = crate::spawn_event_loop!(loop_ctx, {
+            Some(_) = value_in.recv() => {},
+        });
+        let stuck = ctx.spawn(future::pending());
+        tokio::task::yield_now().await;
+
+        assert!(ctx.cancel(&cooperative));
+        assert!(ctx.join_outcome(&cooperative).await.unwrap().is_finished());
+
+        assert!(ctx.cancel(&stuck));
+        tokio::task::yield_now().await;
+        assert_eq!(ctx.handles().get(&stuck).unwrap().state, TaskState::Running);
+
+        assert!(ctx.abort(&stuck));
+        tokio::task::yield_now().await;
+        assert_eq!(ctx.handles().get(&stuck).unwrap().state, TaskState::Aborted);
+        assert!(matches!(ctx.join_outcome(&stuck).await, Some(TaskOutcome::Cancelled)));
+
+        assert!(!ctx.abort(&stuck));
+        assert!(!ctx.cancel(&cooperative));
+    }
+
+    #[tokio::test]
+    async fn fallible_channels() {
+        struct Signal;
+
+        impl Message for Signal {
+            type Channel = WatchChannel<Self>;
+        }
+
+        let ctx = Context::<i32>::new();
+
+        let mut value_in = ctx.try_receiver::<Value>().unwrap();
+        assert!(matches!(ctx.try_receiver::<Value>(), Err(Error::ReceiverTaken(_))));
+        ctx.try_sender::<Value>()
+            .unwrap()
+            .send(Value("value"))
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(value_in.recv().await.unwrap().0, "value");
+        drop(value_in);
+        assert!(matches!(ctx.try_sender::<Value>(), Err(Error::ChannelClosed(_))));
+        let _value_in = ctx.try_receiver::<Value>().unwrap();
+        assert!(ctx.try_sender::<Value>().is_ok());
+
+        let _signal_out = ctx.try_sender::<Signal>().unwrap();
+        assert!(matches!(ctx.try_sender::<Signal>(), Err(Error::SenderTaken(_))));
+
+        assert!(matches!(ctx.try_actor_sender::<Value>(1), Err(Error::UnknownActor(_))));
+        let _actor_in = ctx.try_actor_receiver::<Value>(1).unwrap();
+        assert!(ctx.try_actor_sender::<Value>(1).is_ok());
+
+        let frozen = Context::<i32>::from_system(System::builder().freeze(Freeze::Panic).build());
+        let err = frozen.try_receiver::<Value>().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "undeclared channel `{}` is created in the frozen system",
+                std::any::type_name::<Value>()
+            )
+        );
+    }
 }
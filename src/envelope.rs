@@ -0,0 +1,315 @@
+use std::collections::btree_map::{self, BTreeMap};
+use std::future::Future;
+
+use crate::{
+    BroadcastChannel, Channel, Message, MpmcChannel, MpscChannel, OverflowChannel, Prioritized, PriorityChannel,
+    UnboundedMpscChannel, WatchChannel,
+};
+
+tokio::task_local! {
+    static CURRENT_HEADERS: Headers;
+}
+
+/// Returns the headers of the envelope being handled by the current task, if any.
+pub fn current_headers() -> Headers {
+    CURRENT_HEADERS.try_with(Headers::clone).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(BTreeMap<String, String>);
+
+impl Headers {
+    pub const CORRELATION_ID: &'static str = "correlation-id";
+    pub const DEADLINE: &'static str = "deadline";
+    pub const SENDER: &'static str = "sender";
+    pub const TRACE_PARENT: &'static str = "traceparent";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, String, String> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for Headers {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.0
+            .extend(iter.into_iter().map(|(key, value)| (key.into(), value.into())))
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = (&'a String, &'a String);
+    type IntoIter = btree_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A message with headers. Envelopes created while handling another envelope inherit its headers, so they are
+/// expected to be created right where they are sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<M> {
+    headers: Headers,
+    message: M,
+}
+
+/// A channel of messages `M` able to create a channel of the same kind and configuration for their envelopes.
+pub trait EnvelopeChannel<M>: Channel {
+    type Enveloped: Channel;
+
+    fn enveloped(&self) -> Self::Enveloped;
+}
+
+/// The envelopes go through a channel created the way the channel of the message itself is.
+impl<M: Message> Message for Envelope<M>
+where
+    M::Channel: EnvelopeChannel<M>,
+{
+    type Channel = <M::Channel as EnvelopeChannel<M>>::Enveloped;
+
+    fn create_channel() -> Self::Channel {
+        M::create_channel().enveloped()
+    }
+
+    fn create_actor_channel(actor_id: impl Into<String>) -> Self::Channel {
+        M::create_actor_channel(actor_id).enveloped()
+    }
+}
+
+fn capacity_of(channel: &impl Channel) -> usize {
+    channel.capacity().expect("bounded channels know their capacity")
+}
+
+impl<M: Send + 'static> EnvelopeChannel<M> for MpscChannel<M> {
+    type Enveloped = MpscChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        MpscChannel::new(capacity_of(self))
+    }
+}
+
+impl<M: Send + 'static> EnvelopeChannel<M> for UnboundedMpscChannel<M> {
+    type Enveloped = UnboundedMpscChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        UnboundedMpscChannel::new()
+    }
+}
+
+impl<M: Send + 'static> EnvelopeChannel<M> for MpmcChannel<M> {
+    type Enveloped = MpmcChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        MpmcChannel::new(capacity_of(self))
+    }
+}
+
+impl<M: Send + Clone + 'static> EnvelopeChannel<M> for BroadcastChannel<M> {
+    type Enveloped = BroadcastChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        BroadcastChannel::new(capacity_of(self))
+    }
+}
+
+impl<M: Send + Sync + 'static> EnvelopeChannel<M> for WatchChannel<M> {
+    type Enveloped = WatchChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        WatchChannel::create()
+    }
+}
+
+impl<M: Send + 'static> EnvelopeChannel<M> for OverflowChannel<M> {
+    type Enveloped = OverflowChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        OverflowChannel::new(capacity_of(self), self.overflow())
+    }
+}
+
+impl<M: Prioritized + Send + 'static> Prioritized for Envelope<M> {
+    fn priority(&self) -> u32 {
+        self.message.priority()
+    }
+}
+
+impl<M: Send + 'static> EnvelopeChannel<M> for PriorityChannel<M> {
+    type Enveloped = PriorityChannel<Envelope<M>>;
+
+    fn enveloped(&self) -> Self::Enveloped {
+        let priority = self.priority_fn();
+        PriorityChannel::with_priority(capacity_of(self), move |envelope: &Envelope<M>| {
+            priority(&envelope.message)
+        })
+    }
+}
+
+impl<M> From<M> for Envelope<M> {
+    fn from(message: M) -> Self {
+        Self::new(message)
+    }
+}
+
+impl<M> Envelope<M> {
+    pub fn new(message: M) -> Self {
+        Self {
+            headers: current_headers(),
+            message,
+        }
+    }
+
+    /// Creates an envelope without inheriting the headers of the current one.
+    pub fn detached(message: M) -> Self {
+        Self {
+            headers: Headers::default(),
+            message,
+        }
+    }
+
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    pub fn message(&self) -> &M {
+        &self.message
+    }
+
+    pub fn into_message(self) -> M {
+        self.message
+    }
+
+    pub fn into_parts(self) -> (Headers, M) {
+        (self.headers, self.message)
+    }
+
+    /// Handles the message with the envelope headers set as the current ones.
+    pub async fn handle<F, T>(self, handler: F) -> T::Output
+    where
+        F: FnOnce(M) -> T,
+        T: Future,
+    {
+        CURRENT_HEADERS
+            .scope(self.headers, async move { handler(self.message).await })
+            .await
+    }
+
+    pub fn handle_sync<R>(self, handler: impl FnOnce(M) -> R) -> R {
+        CURRENT_HEADERS.sync_scope(self.headers, || handler(self.message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::envelope::{current_headers, Envelope, Headers};
+    use crate::{ChannelKind, Context, Message, MpscChannel, Overflow, OverflowChannel};
+
+    struct Ping(u32);
+
+    impl Message for Ping {
+        type Channel = MpscChannel<Self>;
+
+        fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+            MpscChannel::new(4)
+        }
+    }
+
+    struct Pong(u32);
+
+    impl Message for Pong {
+        type Channel = OverflowChannel<Self>;
+
+        fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+            OverflowChannel::new(2, Overflow::DropOldest)
+        }
+    }
+
+    #[tokio::test]
+    async fn propagate_headers() {
+        let ctx = Context::<i32>::new();
+        let mut ping_in = ctx.actor_receiver::<Envelope<Ping>>(1);
+        let mut pong_in = ctx.actor_receiver::<Envelope<Pong>>(2);
+        let ping_channel = &ctx.system().channels_of(&1)[0];
+        assert_eq!((ping_channel.kind, ping_channel.capacity), (ChannelKind::Mpsc, Some(4)));
+        assert_eq!(
+            ctx.system().get_actor_channel::<Envelope<Pong>>(&2).unwrap().overflow(),
+            Overflow::DropOldest
+        );
+
+        let pong_out = ctx.actor_sender::<Envelope<Pong>>(2);
+        let actor_ctx = ctx.clone();
+        crate::spawn_event_loop!(actor_ctx, {
+            Some(envelope) = ping_in.recv() => {
+                let pong_out = &pong_out;
+                envelope.handle(|Ping(value)| async move {
+                    let pong = Envelope::new(Pong(value + 1)).with_header(Headers::SENDER, "1");
+                    pong_out.send(pong).await.ok();
+                }).await;
+            },
+        });
+
+        let ping = Envelope::new(Ping(1)).with_header(Headers::CORRELATION_ID, "42");
+        assert_eq!(ping.headers().len(), 1);
+        ctx.actor_sender::<Envelope<Ping>>(1).send(ping).await.ok().unwrap();
+
+        let (headers, Pong(value)) = pong_in.recv().await.unwrap().into_parts();
+        assert_eq!(value, 2);
+        assert_eq!(headers.get(Headers::CORRELATION_ID), Some("42"));
+        assert_eq!(headers.get(Headers::SENDER), Some("1"));
+
+        assert!(current_headers().is_empty());
+        let detached = Envelope::from(Ping(0))
+            .with_header(Headers::CORRELATION_ID, "7")
+            .handle_sync(|_| Envelope::detached(Pong(0)));
+        assert!(detached.headers().is_empty());
+
+        ctx.shutdown().await;
+    }
+}
@@ -0,0 +1,28 @@
+use std::fmt;
+
+use crate::ChannelRef;
+
+/// The failures of the `try_` methods of the [`System`](crate::System) and the [`Context`](crate::Context).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ReceiverTaken(ChannelRef),
+    SenderTaken(ChannelRef),
+    ChannelClosed(ChannelRef),
+    UnknownActor(ChannelRef),
+    /// The channel is not declared in the system frozen with [`Freeze::Panic`](crate::Freeze::Panic).
+    Undeclared(ChannelRef),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReceiverTaken(channel) => write!(f, "receiver of channel {channel} is already taken"),
+            Self::SenderTaken(channel) => write!(f, "sender of channel {channel} is already taken"),
+            Self::ChannelClosed(channel) => write!(f, "channel {channel} is closed"),
+            Self::UnknownActor(channel) => write!(f, "actor of channel {channel} is unknown"),
+            Self::Undeclared(channel) => write!(f, "undeclared channel {channel} is created in the frozen system"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -2,12 +2,27 @@
 
 pub use tokio;
 
+pub use crate::actor::{Actor, ActorHandle};
+pub use crate::actor_ref::ActorRef;
 pub use crate::channel::{
-    BroadcastChannel, Channel, Message, MpscChannel, Receiver, Sender, UnboundedMpscChannel, WatchChannel,
+    AskError, BroadcastChannel, Channel, ChannelInfo, ChannelKind, Message, MpmcChannel, MpmcReceiver, MpmcSender,
+    MpscChannel, MpscReceiver, MpscSender, Overflow, OverflowChannel, OverflowReceiver, OverflowSender, Prioritized,
+    PriorityChannel, PriorityReceiver, PrioritySender, Receiver, Request, RequestChannel, RequestReceiver,
+    RequestSender, Responder, Sender, UnboundedMpscChannel, UnboundedMpscReceiver, UnboundedMpscSender, WatchChannel,
 };
-pub use crate::context::{Context, DefaultActorId, DefaultContext};
+pub use crate::context::{Context, DefaultActorId, DefaultContext, ShutdownReport, TaskBuilder};
 pub use crate::continuous_stream::ContinuousStream;
-pub use crate::system::System;
+pub use crate::envelope::{Envelope, EnvelopeChannel, Headers};
+pub use crate::error::Error;
+pub use crate::metrics::{ChannelCounters, RuntimeCounters};
+#[cfg(feature = "metrics")]
+pub use crate::metrics::{ChannelMetrics, MetricsSnapshot};
+pub use crate::supervisor::{Backoff, RestartIntensity, RestartStrategy, Supervisor};
+pub use crate::system::{
+    ActorStop, ChannelRef, Channels, Freeze, OrphanedChannel, ShutdownPhase, System, SystemBuilder, SystemConfig,
+    ValidationReport,
+};
+pub use crate::task::{CancellationToken, TaskError, TaskFailure, TaskInfo, TaskOutcome, TaskState};
 
 #[macro_export]
 macro_rules! raw_event_loop {
@@ -31,7 +46,7 @@ macro_rules! min_event_loop {
 macro_rules! event_loop {
     ($ctx: expr, { $($select: tt)* }) => {{
         // If shutdown channel exist and close
-        if $ctx.is_channel_closed::<$crate::system::SystemShutdown>().unwrap_or(false) {
+        if $ctx.is_shutdown_channel_closed().unwrap_or(false) {
             drop($ctx);
 
             for _ in 0..1 {
@@ -43,7 +58,9 @@ macro_rules! event_loop {
             }
         } else {
             #[allow(non_snake_case)]
-            let mut crate__shutdown_in_ = $ctx.receiver::<$crate::system::SystemShutdown>();
+            let mut crate__shutdown_in_ = $ctx.shutdown_receiver();
+            #[allow(non_snake_case)]
+            let crate__cancellation_ = $crate::task::current_cancellation_token();
             drop($ctx);
 
             $crate::min_event_loop!({
@@ -54,11 +71,31 @@ macro_rules! event_loop {
                         break
                     }
                 },
+                _ = crate__cancellation_.cancelled() => break,
             });
         }
     }};
 }
 
+/// Runs the event loop until the system shutdown or the actor stop. Only the stops sent after subscribing are
+/// received, so subscribe with `stop_in = ctx.actor_stop_receiver(actor_id)` before spawning to not miss any.
+#[macro_export]
+macro_rules! actor_event_loop {
+    ($ctx: expr, stop_in = $stop_in: expr, { $($select: tt)* }) => {{
+        #[allow(non_snake_case, unused_mut)]
+        let mut crate__actor_stop_in_ = $stop_in;
+
+        $crate::event_loop!($ctx, {
+            $($select)*
+            Ok($crate::system::ActorStop) | Err($crate::tokio::sync::broadcast::error::RecvError::Lagged(_)) =
+                crate__actor_stop_in_.recv() => break,
+        })
+    }};
+    ($ctx: expr, $actor_id: expr, { $($select: tt)* }) => {
+        $crate::actor_event_loop!($ctx, stop_in = $ctx.actor_stop_receiver($actor_id), { $($select)* })
+    };
+}
+
 #[macro_export]
 macro_rules! spawn_raw_event_loop {
     ($ctx: expr, $($select: tt)*) => {
@@ -89,10 +126,35 @@ macro_rules! spawn_event_loop {
     };
 }
 
+#[macro_export]
+macro_rules! spawn_actor_event_loop {
+    ($ctx: expr, $actor_id: expr, { $($select: tt)* }) => {{
+        #[allow(non_snake_case)]
+        let crate__actor_stop_in_ = $ctx.actor_stop_receiver($actor_id);
+        $ctx.clone().spawn(async move {
+            $crate::actor_event_loop!(
+                $ctx,
+                stop_in = crate__actor_stop_in_,
+                { $($select)* }
+            )
+        })
+    }};
+}
+
+pub mod actor;
+pub mod actor_ref;
 pub mod channel;
 pub mod context;
 pub mod continuous_stream;
+pub mod envelope;
+pub mod error;
+pub mod metrics;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+pub mod supervisor;
 pub mod system;
+pub mod task;
+mod trace;
 
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, PartialEq, Ord, Eq)]
 pub struct TaskId(u64);
@@ -0,0 +1,263 @@
+#[cfg(feature = "metrics")]
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+#[cfg(feature = "metrics")]
+use parking_lot::Mutex;
+
+#[cfg(feature = "metrics")]
+use crate::ChannelInfo;
+
+/// Counters of a channel, shared by all of its senders and receivers. Recording is a no-op unless the `metrics`
+/// feature is enabled.
+#[derive(Debug, Default)]
+pub struct ChannelCounters {
+    #[cfg(feature = "metrics")]
+    sent: AtomicU64,
+    #[cfg(feature = "metrics")]
+    received: AtomicU64,
+    #[cfg(feature = "metrics")]
+    failed: AtomicU64,
+    #[cfg(feature = "metrics")]
+    blocked_nanos: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl ChannelCounters {
+    pub fn record<T, E>(&self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.sent.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.failed.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    pub fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn start_blocking(&self) -> BlockingTimer<'_> {
+        BlockingTimer {
+            counters: self,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn blocked(&self) -> Duration {
+        Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+impl ChannelCounters {
+    pub fn record<T, E>(&self, _result: &Result<T, E>) {}
+
+    pub fn record_received(&self) {}
+
+    pub fn start_blocking(&self) -> BlockingTimer<'_> {
+        BlockingTimer { _counters: self }
+    }
+}
+
+/// Adds the time elapsed since its creation to the blocked time of the channel when dropped.
+#[cfg(feature = "metrics")]
+pub struct BlockingTimer<'a> {
+    counters: &'a ChannelCounters,
+    started_at: Instant,
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for BlockingTimer<'_> {
+    fn drop(&mut self) {
+        self.counters.blocked_nanos.fetch_add(
+            u64::try_from(self.started_at.elapsed().as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub struct BlockingTimer<'a> {
+    _counters: &'a ChannelCounters,
+}
+
+/// Counters of the system as a whole. Recording is a no-op unless the `metrics` feature is enabled.
+#[derive(Debug, Default)]
+pub struct RuntimeCounters {
+    #[cfg(feature = "metrics")]
+    restarts: Mutex<BTreeMap<String, u64>>,
+    #[cfg(feature = "metrics")]
+    last_shutdown_nanos: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl RuntimeCounters {
+    pub fn record_restart(&self, actor_id: &impl fmt::Display) {
+        *self.restarts.lock().entry(actor_id.to_string()).or_default() += 1;
+    }
+
+    pub fn record_shutdown(&self, duration: Duration) {
+        self.last_shutdown_nanos.store(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn restarts(&self) -> Vec<(String, u64)> {
+        self.restarts
+            .lock()
+            .iter()
+            .map(|(actor_id, count)| (actor_id.clone(), *count))
+            .collect()
+    }
+
+    pub fn last_shutdown(&self) -> Option<Duration> {
+        match self.last_shutdown_nanos.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+impl RuntimeCounters {
+    pub fn record_restart(&self, _actor_id: &impl fmt::Display) {}
+
+    pub fn record_shutdown(&self, _duration: Duration) {}
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelMetrics {
+    pub actor_id: Option<String>,
+    pub info: ChannelInfo,
+    /// `false` for the broadcast and watch channels, which hand out the tokio halves as is.
+    /// Their messages are not counted, and the counts below stay zero.
+    pub counted: bool,
+    pub sent: u64,
+    pub received: u64,
+    pub failed: u64,
+    pub blocked: Duration,
+}
+
+#[cfg(feature = "metrics")]
+impl ChannelMetrics {
+    pub(crate) fn new(actor_id: Option<String>, info: ChannelInfo, counters: Option<&ChannelCounters>) -> Self {
+        Self {
+            actor_id,
+            info,
+            counted: counters.is_some(),
+            sent: counters.map_or(0, ChannelCounters::sent),
+            received: counters.map_or(0, ChannelCounters::received),
+            failed: counters.map_or(0, ChannelCounters::failed),
+            blocked: counters.map_or(Duration::ZERO, ChannelCounters::blocked),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub actors: usize,
+    pub channels: Vec<ChannelMetrics>,
+    pub restarts: Vec<(String, u64)>,
+    pub last_shutdown: Option<Duration>,
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{BroadcastChannel, ChannelKind, Context, Message, MpscChannel, UnboundedMpscChannel};
+
+    struct Value(u32);
+
+    #[derive(Clone)]
+    struct Notice;
+
+    impl Message for Notice {
+        type Channel = BroadcastChannel<Self>;
+    }
+
+    struct Event;
+
+    impl Message for Event {
+        type Channel = UnboundedMpscChannel<Self>;
+    }
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+
+        fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+            MpscChannel::new(2)
+        }
+    }
+
+    #[tokio::test]
+    async fn channel_metrics() {
+        let ctx = Context::<i32>::new();
+        let value_out = ctx.actor_sender::<Value>(1);
+        let mut value_in = ctx.actor_receiver::<Value>(1);
+
+        value_out.send(Value(1)).await.ok().unwrap();
+        value_out.send(Value(2)).await.ok().unwrap();
+        assert!(value_out.try_send(Value(3)).is_err());
+        assert!(value_out
+            .send_timeout(Value(4), Duration::from_millis(5))
+            .await
+            .is_err());
+        assert_eq!(value_in.recv().await.unwrap().0, 1);
+
+        let snapshot = ctx.system().metrics();
+        assert_eq!(snapshot.channels.len(), 1);
+
+        let metrics = &snapshot.channels[0];
+        assert_eq!(metrics.actor_id.as_deref(), Some("1"));
+        assert_eq!(metrics.info.kind, ChannelKind::Mpsc);
+        assert_eq!(metrics.info.capacity, Some(2));
+        assert_eq!(metrics.info.queue_len, Some(1));
+        assert_eq!(metrics.sent, 2);
+        assert_eq!(metrics.received, 1);
+        assert_eq!(metrics.failed, 2);
+        assert!(metrics.blocked >= Duration::from_millis(5));
+
+        let event_out = ctx.sender::<Event>();
+        let mut event_in = ctx.receiver::<Event>();
+        event_out.send(Event).ok().unwrap();
+        event_out.send(Event).ok().unwrap();
+        event_in.recv().await.unwrap();
+
+        let metrics = &ctx.system().metrics().channels[0];
+        assert_eq!(metrics.info.queue_len, None);
+        assert_eq!((metrics.sent, metrics.received), (2, 1));
+
+        let _notice_in = ctx.receiver::<Notice>();
+        ctx.sender::<Notice>().send(Notice).ok().unwrap();
+        let snapshot = ctx.system().metrics();
+        let notice = snapshot
+            .channels
+            .iter()
+            .find(|metrics| metrics.info.kind == ChannelKind::Broadcast)
+            .unwrap();
+        assert_eq!(notice.info.queue_len, Some(1));
+        assert!(!notice.counted);
+        assert_eq!(notice.sent, 0);
+        assert_eq!(snapshot.channels.iter().filter(|metrics| metrics.counted).count(), 2);
+    }
+}
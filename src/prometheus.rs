@@ -0,0 +1,176 @@
+//! Rendering of the runtime statistics in the Prometheus text exposition format.
+
+use std::fmt::{self, Write};
+
+use crate::{ChannelMetrics, Context, TaskState};
+
+pub fn render<ActorId: fmt::Display>(ctx: &Context<ActorId>) -> String {
+    let mut output = String::new();
+    write_metrics(&mut output, ctx).expect("writing to a string never fails");
+    output
+}
+
+pub fn write_metrics<ActorId: fmt::Display>(output: &mut impl Write, ctx: &Context<ActorId>) -> fmt::Result {
+    let tasks = ctx.tasks();
+    header(output, "truba_tasks", "gauge", "Number of tracked tasks by state.")?;
+    for (state, label) in [
+        (TaskState::Running, "running"),
+        (TaskState::Finished, "finished"),
+        (TaskState::Failed, "failed"),
+        (TaskState::Aborted, "aborted"),
+    ] {
+        let count = tasks.iter().filter(|task| task.state == state).count();
+        writeln!(output, "truba_tasks{{state=\"{label}\"}} {count}")?;
+    }
+
+    let snapshot = ctx.system().metrics();
+    header(
+        output,
+        "truba_actors",
+        "gauge",
+        "Number of actors registered in the system.",
+    )?;
+    writeln!(output, "truba_actors {}", snapshot.actors)?;
+
+    channel_family(
+        output,
+        &snapshot.channels,
+        (
+            "truba_channel_queue_depth",
+            "gauge",
+            "Number of messages waiting in the channel.",
+        ),
+        |channel| channel.info.queue_len.map(|len| len.to_string()),
+    )?;
+    channel_family(
+        output,
+        &snapshot.channels,
+        ("truba_channel_capacity", "gauge", "Capacity of the bounded channel."),
+        |channel| channel.info.capacity.map(|capacity| capacity.to_string()),
+    )?;
+    channel_family(
+        output,
+        &snapshot.channels,
+        (
+            "truba_channel_sent_total",
+            "counter",
+            "Number of messages sent to the channel.",
+        ),
+        |channel| channel.counted.then(|| channel.sent.to_string()),
+    )?;
+    channel_family(
+        output,
+        &snapshot.channels,
+        (
+            "truba_channel_failed_total",
+            "counter",
+            "Number of failed sends to the channel.",
+        ),
+        |channel| channel.counted.then(|| channel.failed.to_string()),
+    )?;
+    channel_family(
+        output,
+        &snapshot.channels,
+        (
+            "truba_channel_blocked_seconds_total",
+            "counter",
+            "Time spent by senders waiting for the channel capacity.",
+        ),
+        |channel| channel.counted.then(|| channel.blocked.as_secs_f64().to_string()),
+    )?;
+
+    header(
+        output,
+        "truba_actor_restarts_total",
+        "counter",
+        "Number of actor restarts by supervisors.",
+    )?;
+    for (actor_id, restarts) in &snapshot.restarts {
+        writeln!(
+            output,
+            "truba_actor_restarts_total{{actor=\"{}\"}} {restarts}",
+            escape(actor_id)
+        )?;
+    }
+
+    if let Some(duration) = snapshot.last_shutdown {
+        header(
+            output,
+            "truba_shutdown_duration_seconds",
+            "gauge",
+            "Duration of the last shutdown.",
+        )?;
+        writeln!(output, "truba_shutdown_duration_seconds {}", duration.as_secs_f64())?;
+    }
+    Ok(())
+}
+
+fn header(output: &mut impl Write, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(output, "# HELP {name} {help}")?;
+    writeln!(output, "# TYPE {name} {kind}")
+}
+
+fn channel_family(
+    output: &mut impl Write,
+    channels: &[ChannelMetrics],
+    (name, kind, help): (&str, &str, &str),
+    value: impl Fn(&ChannelMetrics) -> Option<String>,
+) -> fmt::Result {
+    header(output, name, kind, help)?;
+    for channel in channels {
+        if let Some(value) = value(channel) {
+            write!(output, "{name}{{message=\"{}\"", escape(channel.info.message))?;
+            if let Some(actor_id) = &channel.actor_id {
+                write!(output, ",actor=\"{}\"", escape(actor_id))?;
+            }
+            writeln!(output, "}} {value}")?;
+        }
+    }
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BroadcastChannel, Context, Message, MpscChannel};
+
+    struct Value;
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+    }
+
+    #[derive(Clone)]
+    struct Notice;
+
+    impl Message for Notice {
+        type Channel = BroadcastChannel<Self>;
+    }
+
+    #[tokio::test]
+    async fn render_metrics() {
+        let ctx = Context::<String>::new();
+        let _value_in = ctx.actor_receiver::<Value>("say \"hi\"");
+        ctx.actor_sender::<Value>("say \"hi\"").send(Value).await.ok().unwrap();
+        let _notice_in = ctx.receiver::<Notice>();
+        ctx.sender::<Notice>().send(Notice).ok().unwrap();
+        ctx.spawn(futures::future::pending());
+        ctx.system().runtime_counters().record_restart(&"worker");
+
+        let output = super::render(&ctx);
+        assert!(output.contains("# TYPE truba_tasks gauge\n"));
+        assert!(output.contains("truba_tasks{state=\"running\"} 1\n"));
+        assert!(output.contains("truba_actors 1\n"));
+        assert!(output.contains(
+            "truba_channel_queue_depth{message=\"truba::prometheus::tests::Value\",actor=\"say \\\"hi\\\"\"} 1\n"
+        ));
+        assert!(output.contains("truba_channel_sent_total{message=\"truba::prometheus::tests::Value\""));
+        assert!(output.contains("truba_channel_queue_depth{message=\"truba::prometheus::tests::Notice\"} 1\n"));
+        assert!(!output.contains("truba_channel_sent_total{message=\"truba::prometheus::tests::Notice\""));
+        assert!(output.contains("truba_actor_restarts_total{actor=\"worker\"} 1\n"));
+        assert!(!output.contains("truba_shutdown_duration_seconds"));
+    }
+}
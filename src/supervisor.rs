@@ -0,0 +1,417 @@
+use std::any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use futures::FutureExt;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+
+use crate::system::ActorStop;
+use crate::{task, Actor, Context, Receiver, TaskId};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RestartStrategy {
+    OneForOne,
+    OneForAll,
+    RestForOne,
+}
+
+impl RestartStrategy {
+    fn affected(&self, index: usize, len: usize) -> Range<usize> {
+        match self {
+            Self::OneForOne => index..index + 1,
+            Self::OneForAll => 0..len,
+            Self::RestForOne => index..len,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RestartIntensity {
+    pub max_restarts: usize,
+    pub within: Duration,
+}
+
+impl Default for RestartIntensity {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            within: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Backoff {
+    #[default]
+    None,
+    Fixed(Duration),
+    Exponential {
+        initial: Duration,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Self::None => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Exponential { initial, max } => initial
+                .checked_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+                .map_or(max, |delay| delay.min(max)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ChildExit {
+    Stopped,
+    Panicked,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ChildCommand {
+    Restart,
+    Stop,
+}
+
+struct ChildLink {
+    index: usize,
+    exits: mpsc::UnboundedSender<(usize, ChildExit)>,
+    commands: mpsc::UnboundedReceiver<ChildCommand>,
+}
+
+type ChildStarter<Id> = Box<dyn FnOnce(&Context<Id>, ChildLink) -> TaskId + Send>;
+
+pub struct Supervisor<Id> {
+    strategy: RestartStrategy,
+    intensity: RestartIntensity,
+    backoff: Backoff,
+    children: Vec<ChildStarter<Id>>,
+}
+
+impl<Id: Eq + Hash + fmt::Display + Clone + Send + Sync + 'static> Supervisor<Id> {
+    pub fn new(strategy: RestartStrategy) -> Self {
+        Self {
+            strategy,
+            intensity: Default::default(),
+            backoff: Default::default(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn intensity(mut self, max_restarts: usize, within: Duration) -> Self {
+        self.intensity = RestartIntensity { max_restarts, within };
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn child<A, F>(mut self, actor_id: impl Into<Id>, factory: F) -> Self
+    where
+        A: Actor<Id = Id>,
+        F: FnMut() -> A + Send + 'static,
+    {
+        let actor_id = actor_id.into();
+        self.children.push(Box::new(move |ctx, link| {
+            let stop_in = ctx.actor_stop_receiver(actor_id.clone());
+            ctx.task().name(any::type_name::<A>()).actor(&actor_id).spawn(run_child(
+                ctx.clone(),
+                actor_id,
+                stop_in,
+                factory,
+                link,
+            ))
+        }));
+        self
+    }
+
+    pub fn start(self, ctx: &Context<Id>) -> TaskId {
+        let (exits_sender, exits) = mpsc::unbounded_channel();
+        let commands = self
+            .children
+            .into_iter()
+            .enumerate()
+            .map(|(index, start)| {
+                let (commands_sender, commands) = mpsc::unbounded_channel();
+                start(
+                    ctx,
+                    ChildLink {
+                        index,
+                        exits: exits_sender.clone(),
+                        commands,
+                    },
+                );
+                commands_sender
+            })
+            .collect();
+
+        ctx.task().name("supervisor").spawn(supervise(
+            ctx.clone(),
+            self.strategy,
+            self.intensity,
+            self.backoff,
+            exits,
+            commands,
+        ))
+    }
+}
+
+async fn run_child<A, F>(
+    ctx: Context<A::Id>,
+    actor_id: A::Id,
+    mut stop_in: Receiver<ActorStop>,
+    mut factory: F,
+    mut link: ChildLink,
+) where
+    A: Actor,
+    F: FnMut() -> A + Send + 'static,
+{
+    // The inbox outlives the actor instances, so senders obtained before a crash keep working after the restart
+    let mut inbox = A::inbox(&ctx, &actor_id);
+    let mut restarted = false;
+
+    loop {
+        if restarted {
+            ctx.system().runtime_counters().record_restart(&actor_id);
+        }
+        restarted = true;
+
+        let result = AssertUnwindSafe(async {
+            let mut actor = factory();
+            let mut restart = false;
+
+            actor.started(&ctx);
+            let loop_ctx = ctx.clone();
+            crate::actor_event_loop!(loop_ctx, stop_in = &mut stop_in, {
+                message = A::receive(&mut inbox) => {
+                    let Some(message) = message else {
+                        break;
+                    };
+                    if actor.handle(message).await.is_break() {
+                        break;
+                    }
+                },
+                command = link.commands.recv() => {
+                    restart = command == Some(ChildCommand::Restart);
+                    break;
+                },
+            });
+
+            actor.stopping(&mut inbox);
+            actor.stopped();
+            restart
+        })
+        .catch_unwind()
+        .await;
+
+        let exit = match result {
+            Ok(true) => continue,
+            Ok(false) => ChildExit::Stopped,
+            Err(_) => ChildExit::Panicked,
+        };
+
+        if link.exits.send((link.index, exit)).is_err() || exit == ChildExit::Stopped {
+            break;
+        }
+
+        if link.commands.recv().await != Some(ChildCommand::Restart) {
+            break;
+        }
+    }
+}
+
+async fn supervise<Id>(
+    ctx: Context<Id>,
+    strategy: RestartStrategy,
+    intensity: RestartIntensity,
+    backoff: Backoff,
+    mut exits: mpsc::UnboundedReceiver<(usize, ChildExit)>,
+    commands: Vec<mpsc::UnboundedSender<ChildCommand>>,
+) {
+    if commands.is_empty() {
+        return;
+    }
+
+    let mut running = vec![true; commands.len()];
+    let mut restarts = VecDeque::new();
+    // The backoff waits inside a branch, so the shutdown and the cancellation are raced with it separately
+    let mut shutdown_in = ctx.shutdown_receiver();
+    let cancellation = task::current_cancellation_token();
+
+    crate::event_loop!(ctx, {
+        Some((index, exit)) = exits.recv() => {
+            running[index] = false;
+
+            if exit == ChildExit::Panicked {
+                let now = Instant::now();
+                while restarts.front().is_some_and(|restarted_at| now.duration_since(*restarted_at) > intensity.within) {
+                    restarts.pop_front();
+                }
+
+                if restarts.len() >= intensity.max_restarts {
+                    for command in &commands {
+                        command.send(ChildCommand::Stop).ok();
+                    }
+                    break;
+                }
+
+                restarts.push_back(now);
+                let interrupted = tokio::select! {
+                    _ = time::sleep(backoff.delay(restarts.len() as u32)) => false,
+                    _ = shutdown_in.wait_for(Option::is_some) => true,
+                    _ = cancellation.cancelled() => true,
+                };
+                if interrupted {
+                    break;
+                }
+
+                for affected in strategy.affected(index, commands.len()) {
+                    if commands[affected].send(ChildCommand::Restart).is_ok() {
+                        running[affected] = true;
+                    }
+                }
+            }
+
+            if !running.contains(&true) {
+                break;
+            }
+        },
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use crate::supervisor::{Backoff, RestartStrategy, Supervisor};
+    use crate::{Actor, Context, Message, MpscChannel, Receiver};
+
+    struct Value(u32);
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+    }
+
+    struct Crashing {
+        starts: Arc<AtomicU32>,
+        values: mpsc::UnboundedSender<u32>,
+    }
+
+    impl Actor for Crashing {
+        type Id = i32;
+        type Inbox = Receiver<Value>;
+        type Message = Value;
+
+        fn inbox(ctx: &Context<i32>, id: &i32) -> Self::Inbox {
+            ctx.actor_receiver::<Value>(*id)
+        }
+
+        async fn receive(inbox: &mut Self::Inbox) -> Option<Value> {
+            inbox.recv().await
+        }
+
+        async fn handle(&mut self, Value(value): Value) -> ControlFlow<()> {
+            if value == 0 {
+                panic!("crash");
+            }
+            self.values.send(value).ok();
+            ControlFlow::Continue(())
+        }
+
+        fn started(&mut self, _ctx: &Context<i32>) {
+            self.starts.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn child(
+        starts: &Arc<AtomicU32>,
+        values: &mpsc::UnboundedSender<u32>,
+    ) -> impl FnMut() -> Crashing + Send + 'static {
+        let starts = starts.clone();
+        let values = values.clone();
+        move || Crashing {
+            starts: starts.clone(),
+            values: values.clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_one_for_all() {
+        let ctx = Context::<i32>::new();
+        let starts = Arc::new(AtomicU32::new(0));
+        let (values, mut values_in) = mpsc::unbounded_channel();
+
+        Supervisor::new(RestartStrategy::OneForAll)
+            .backoff(Backoff::Fixed(Duration::from_millis(1)))
+            .child(1, child(&starts, &values))
+            .child(2, child(&starts, &values))
+            .start(&ctx);
+
+        let sender = ctx.actor_sender::<Value>(1);
+        sender.send(Value(1)).await.ok().unwrap();
+        assert_eq!(values_in.recv().await, Some(1));
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+
+        sender.send(Value(0)).await.ok().unwrap();
+        sender.send(Value(2)).await.ok().unwrap();
+        assert_eq!(values_in.recv().await, Some(2));
+
+        ctx.shutdown().await;
+        assert_eq!(starts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn give_up_after_max_restarts() {
+        let ctx = Context::<i32>::new();
+        let starts = Arc::new(AtomicU32::new(0));
+        let (values, _values_in) = mpsc::unbounded_channel();
+
+        let supervisor = Supervisor::new(RestartStrategy::OneForOne)
+            .intensity(2, Duration::from_secs(60))
+            .child(1, child(&starts, &values))
+            .start(&ctx);
+
+        let sender = ctx.actor_sender::<Value>(1);
+        for _ in 0..3 {
+            sender.send(Value(0)).await.ok().unwrap();
+        }
+
+        ctx.join(&supervisor).await.unwrap();
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+
+        ctx.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn shutdown_during_backoff() {
+        let ctx = Context::<i32>::new();
+        let starts = Arc::new(AtomicU32::new(0));
+        let (values, _values_in) = mpsc::unbounded_channel();
+
+        Supervisor::new(RestartStrategy::OneForOne)
+            .backoff(Backoff::Fixed(Duration::from_secs(60)))
+            .child(1, child(&starts, &values))
+            .start(&ctx);
+        ctx.actor_sender::<Value>(1).send(Value(0)).await.ok().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        tokio::time::timeout(Duration::from_secs(1), ctx.shutdown())
+            .await
+            .unwrap();
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+    }
+}
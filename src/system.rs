@@ -1,12 +1,31 @@
-use std::collections::HashMap;
+use std::any::{self, TypeId};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::future::Future;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
 
-use typemap_ors::{Entry, Key, SendMap};
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
+use typemap_ors::{Key, ShareMap};
 
-use crate::{Channel, Context, Message, TaskId, WatchChannel};
+use crate::metrics::RuntimeCounters;
+#[cfg(feature = "metrics")]
+use crate::metrics::{ChannelMetrics, MetricsSnapshot};
+use crate::task::{TaskFailure, TaskHook};
+use crate::{trace, Error};
+use crate::{
+    BroadcastChannel, Channel, ChannelCounters, ChannelInfo, Context, Message, Receiver, TaskId, WatchChannel,
+};
+
+pub use self::builder::{ChannelRef, Freeze, OrphanedChannel, SystemBuilder, ValidationReport};
+pub use self::config::SystemConfig;
+
+pub mod builder;
+pub mod config;
 
 struct ChannelKey<M>(PhantomData<M>);
 
@@ -14,68 +33,217 @@ impl<M: Message> Key for ChannelKey<M> {
     type Value = M::Channel;
 }
 
-struct Channels(SendMap);
+type Describe = fn(&Channels) -> Option<(ChannelInfo, Option<&ChannelCounters>)>;
+
+/// The channels registered for a single actor or the whole system, keyed by the message type.
+pub struct Channels {
+    map: ShareMap,
+    describers: HashMap<TypeId, Describe>,
+}
 
 impl Default for Channels {
     fn default() -> Self {
-        Self(SendMap::custom())
+        Self {
+            map: ShareMap::custom(),
+            describers: Default::default(),
+        }
     }
 }
 
+fn describe<M: Message>(channels: &Channels) -> Option<(ChannelInfo, Option<&ChannelCounters>)> {
+    channels.get::<M>().map(|channel| {
+        let info = ChannelInfo {
+            message: any::type_name::<M>(),
+            kind: <M::Channel as Channel>::KIND,
+            capacity: channel.capacity(),
+            queue_len: channel.queue_len(),
+            is_closed: channel.is_closed(),
+            receiver_taken: channel.is_receiver_taken(),
+            senders: channel.sender_count(),
+        };
+        (info, channel.counters())
+    })
+}
+
 impl Channels {
-    fn sender<M: Message>(&mut self, constructor: impl FnOnce() -> M::Channel) -> <M::Channel as Channel>::Sender {
-        match self.0.entry::<ChannelKey<M>>() {
-            Entry::Occupied(entry) => {
-                let channel = entry.get();
-                let sender = channel.sender();
-
-                if !channel.is_closed() {
-                    sender
-                } else {
-                    self.0.insert::<ChannelKey<M>>(constructor());
-                    self.0
-                        .get::<ChannelKey<M>>()
-                        .expect("always present, just added before")
-                        .sender()
-                }
-            },
-            Entry::Vacant(entry) => entry.insert(constructor()).sender(),
+    /// Takes a sender or a receiver from an open (or reopened in place) channel.
+    fn resolve<M: Message, T>(&self, take: &impl Fn(&M::Channel) -> T) -> Option<T> {
+        let channel = self.map.get::<ChannelKey<M>>()?;
+        let value = take(channel);
+
+        if !channel.is_closed() {
+            Some(value)
+        } else if channel.reopen() {
+            Some(take(channel))
+        } else {
+            None
         }
     }
 
-    fn receiver<M: Message>(&mut self, constructor: impl FnOnce() -> M::Channel) -> <M::Channel as Channel>::Receiver {
-        match self.0.entry::<ChannelKey<M>>() {
-            Entry::Occupied(entry) => {
-                let channel = entry.get();
-                let receiver = channel.receiver();
-
-                if !channel.is_closed() {
-                    receiver
-                } else {
-                    self.0.insert::<ChannelKey<M>>(constructor());
-                    self.0
-                        .get::<ChannelKey<M>>()
-                        .expect("always present, just added before")
-                        .receiver()
-                }
-            },
-            Entry::Vacant(entry) => entry.insert(constructor()).receiver(),
+    fn resolve_or_insert<M: Message, T>(
+        &mut self,
+        constructor: impl FnOnce() -> M::Channel,
+        take: &impl Fn(&M::Channel) -> T,
+    ) -> T {
+        if let Some(value) = self.resolve::<M, T>(take) {
+            return value;
         }
+
+        self.map.insert::<ChannelKey<M>>(constructor());
+        self.describers.insert(TypeId::of::<M>(), describe::<M>);
+        take(
+            self.map
+                .get::<ChannelKey<M>>()
+                .expect("always present, just added before"),
+        )
     }
 
-    fn get<M: Message>(&self) -> Option<&M::Channel> {
-        self.0.get::<ChannelKey<M>>()
+    pub fn get<M: Message>(&self) -> Option<&M::Channel> {
+        self.map.get::<ChannelKey<M>>()
     }
 
-    fn remove<M: Message>(&mut self) -> Option<M::Channel> {
-        self.0.remove::<ChannelKey<M>>()
+    pub fn remove<M: Message>(&mut self) -> Option<M::Channel> {
+        self.describers.remove(&TypeId::of::<M>());
+        self.map.remove::<ChannelKey<M>>()
     }
 
     fn clear(&mut self) {
-        self.0.clear()
+        self.describers.clear();
+        self.map.clear()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `true` if every channel is closed, including when there are no channels at all.
+    pub fn is_closed(&self) -> bool {
+        self.describers
+            .values()
+            .filter_map(|describe| describe(self))
+            .all(|(info, _)| info.is_closed)
+    }
+
+    pub fn infos(&self) -> Vec<ChannelInfo> {
+        let mut infos: Vec<_> = self
+            .describers
+            .values()
+            .filter_map(|describe| describe(self))
+            .map(|(info, _)| info)
+            .collect();
+        infos.sort_by_key(|info| info.message);
+        infos
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics(&self, actor_id: Option<String>) -> Vec<ChannelMetrics> {
+        let mut metrics: Vec<_> = self
+            .describers
+            .values()
+            .filter_map(|describe| describe(self))
+            .map(|(info, counters)| ChannelMetrics::new(actor_id.clone(), info, counters))
+            .collect();
+        metrics.sort_by_key(|metrics| metrics.info.message);
+        metrics
     }
 }
 
+/// Per-actor channels, split into independently locked shards by the actor id hash.
+struct ActorChannels<ActorId> {
+    hasher: RandomState,
+    shards: Box<[RwLock<HashMap<ActorId, Channels>>]>,
+}
+
+impl<ActorId> ActorChannels<ActorId> {
+    fn new(shards: usize) -> Self {
+        Self {
+            hasher: RandomState::new(),
+            shards: (0..shards.max(1).next_power_of_two())
+                .map(|_| Default::default())
+                .collect(),
+        }
+    }
+}
+
+impl<ActorId> Default for ActorChannels<ActorId> {
+    fn default() -> Self {
+        let parallelism = thread::available_parallelism().map_or(1, |parallelism| parallelism.get());
+        Self::new(parallelism * 4)
+    }
+}
+
+impl<ActorId: Eq + Hash> ActorChannels<ActorId> {
+    fn shard(&self, actor_id: &ActorId) -> &RwLock<HashMap<ActorId, Channels>> {
+        let hash = self.hasher.hash_one(actor_id) as usize;
+        &self.shards[hash & (self.shards.len() - 1)]
+    }
+
+    fn get_or_create<M: Message, T>(
+        &self,
+        actor_id: ActorId,
+        constructor: impl FnOnce() -> M::Channel,
+        take: impl Fn(&M::Channel) -> T,
+        on_create: impl FnOnce() -> Result<(), Error>,
+    ) -> Result<T, Error> {
+        let shard = self.shard(&actor_id);
+        if let Some(value) = shard
+            .read()
+            .get(&actor_id)
+            .and_then(|channels| channels.resolve::<M, T>(&take))
+        {
+            return Ok(value);
+        }
+
+        // Checked before the entry is made, so a rejected channel leaves no empty actor behind
+        let mut shard = shard.write();
+        if shard.get(&actor_id).and_then(|channels| channels.get::<M>()).is_none() {
+            on_create()?;
+        }
+        Ok(shard
+            .entry(actor_id)
+            .or_default()
+            .resolve_or_insert::<M, T>(constructor, &take))
+    }
+
+    fn contains(&self, actor_id: &ActorId) -> bool {
+        self.shard(actor_id).read().contains_key(actor_id)
+    }
+}
+
+fn filter_channels(
+    channels: &Channels,
+    actor_id: Option<String>,
+    filter: impl Fn(&ChannelInfo) -> bool,
+) -> Vec<(ChannelRef, ChannelInfo)> {
+    channels
+        .infos()
+        .into_iter()
+        .filter(filter)
+        .map(|info| {
+            let channel = ChannelRef {
+                message: info.message,
+                actor_id: actor_id.clone(),
+            };
+            (channel, info)
+        })
+        .collect()
+}
+
+type Taken<M> = Result<<<M as Message>::Channel as Channel>::Receiver, <<M as Message>::Channel as Channel>::Receiver>;
+
+fn take_receiver<M: Message>(channel: &M::Channel) -> Taken<M> {
+    channel.try_receiver().ok_or_else(|| channel.receiver())
+}
+
+// Only the frozen system fails to create a channel, and it is expected to panic then
+fn infallible<T>(result: Result<T, Error>) -> T {
+    result.unwrap_or_else(|err| panic!("{err}"))
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SystemShutdown;
 
@@ -83,112 +251,451 @@ impl Message for SystemShutdown {
     type Channel = WatchChannel<Self>;
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ActorStop;
+
+// Every event loop of the actor subscribes, and only the stops sent while it is running reach it
+impl Message for ActorStop {
+    type Channel = BroadcastChannel<Self>;
+
+    fn create_channel() -> Self::Channel {
+        BroadcastChannel::new(1)
+    }
+
+    fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+        BroadcastChannel::new(1)
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Hash, PartialOrd, PartialEq, Ord, Eq)]
+pub struct ShutdownPhase(pub u32);
+
+/// The registry of channels and tasks bookkeeping. All methods take `&self` and lock only the touched part:
+/// the global channels, a single shard of the actor channels, or nothing at all for the task ids.
 pub struct System<ActorId> {
-    next_task_id: TaskId,
-    channels: Channels,
-    actor_channels: HashMap<ActorId, Channels>,
+    next_task_id: AtomicU64,
+    channels: RwLock<Channels>,
+    actor_channels: ActorChannels<ActorId>,
+    shutdown_phases: RwLock<BTreeMap<ShutdownPhase, WatchChannel<SystemShutdown>>>,
+    task_hook: Arc<RwLock<Option<TaskHook>>>,
+    runtime_counters: RuntimeCounters,
+    config: SystemConfig,
+    freeze: RwLock<Option<Freeze>>,
+    undeclared: Mutex<Vec<ChannelRef>>,
+    receivers_taken_again: Mutex<Vec<ChannelRef>>,
 }
 
 impl<ActorId> Default for System<ActorId> {
     fn default() -> Self {
         Self {
-            next_task_id: TaskId(1),
+            next_task_id: AtomicU64::new(1),
             channels: Default::default(),
             actor_channels: Default::default(),
+            shutdown_phases: Default::default(),
+            task_hook: Default::default(),
+            runtime_counters: Default::default(),
+            config: Default::default(),
+            freeze: Default::default(),
+            undeclared: Default::default(),
+            receivers_taken_again: Default::default(),
+        }
+    }
+}
+
+impl<ActorId> System<ActorId> {
+    pub fn with_config(config: SystemConfig) -> Self {
+        Self {
+            actor_channels: config.get_actor_shards().map(ActorChannels::new).unwrap_or_default(),
+            config,
+            ..Default::default()
         }
     }
+
+    pub fn with_actor_shards(shards: usize) -> Self {
+        Self::with_config(SystemConfig::new().actor_shards(shards))
+    }
+
+    pub fn builder() -> SystemBuilder<ActorId> {
+        SystemBuilder::new()
+    }
+
+    pub fn config(&self) -> &SystemConfig {
+        &self.config
+    }
+
+    /// Forbids the lazy creation of the channels not existing at the moment of the call,
+    /// except the channels for the system's own messages.
+    pub fn freeze(&self, freeze: Freeze) {
+        *self.freeze.write() = Some(freeze);
+    }
+
+    pub fn unfreeze(&self) {
+        *self.freeze.write() = None;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.freeze.read().is_some()
+    }
+
+    fn check_declared<M: Message>(&self, actor_id: Option<String>) -> Result<(), Error> {
+        let Some(freeze) = *self.freeze.read() else {
+            return Ok(());
+        };
+        if [TypeId::of::<SystemShutdown>(), TypeId::of::<ActorStop>()].contains(&TypeId::of::<M>()) {
+            return Ok(());
+        }
+
+        let channel = ChannelRef::new::<M>(actor_id);
+        match freeze {
+            Freeze::Panic => Err(Error::Undeclared(channel)),
+            Freeze::Report => {
+                self.undeclared.lock().push(channel);
+                Ok(())
+            },
+        }
+    }
+
+    /// Hands out a disconnected receiver instead of the one taken before, warning once per channel.
+    fn receiver_taken_again<M: Message>(
+        &self,
+        actor_id: Option<String>,
+        taken: Taken<M>,
+    ) -> <M::Channel as Channel>::Receiver {
+        taken.unwrap_or_else(|disconnected| {
+            let channel = ChannelRef::new::<M>(actor_id);
+            let mut taken_again = self.receivers_taken_again.lock();
+            if !taken_again.contains(&channel) {
+                trace::receiver_taken_again(&channel);
+                taken_again.push(channel);
+            }
+            disconnected
+        })
+    }
+}
+
+impl<ActorId: Clone> System<ActorId> {
+    pub fn actor_ids(&self) -> Vec<ActorId> {
+        self.actor_channels
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read().keys().cloned().collect::<Vec<_>>())
+            .collect()
+    }
 }
 
 impl<ActorId: Eq + Hash> System<ActorId> {
-    pub fn get_actor_channel<M: Message>(&self, actor_id: &ActorId) -> Option<&M::Channel> {
-        self.actor_channels.get(actor_id)?.get::<M>()
+    pub fn channels_of(&self, actor_id: &ActorId) -> Vec<ChannelInfo> {
+        self.actor_channels
+            .shard(actor_id)
+            .read()
+            .get(actor_id)
+            .map(Channels::infos)
+            .unwrap_or_default()
+    }
+
+    pub fn extract_actor(&self, actor_id: &ActorId) -> Option<Channels> {
+        self.actor_channels.shard(actor_id).write().remove(actor_id)
     }
 
-    pub fn extract_actor_channel<M: Message>(&mut self, actor_id: &ActorId) -> Option<M::Channel> {
-        self.actor_channels.get_mut(actor_id)?.remove::<M>()
+    /// Removes the actors whose channels are all closed. Senders taken from the removed channels
+    /// do not follow the channels created for the same actor later, unlike the ones from [`crate::ActorRef`].
+    pub fn prune_closed(&self) -> Vec<ActorId> {
+        self.prune_closed_if(|_| true)
+    }
+
+    pub fn prune_closed_if(&self, mut prunable: impl FnMut(&ActorId) -> bool) -> Vec<ActorId> {
+        let mut pruned = Vec::new();
+        for shard in self.actor_channels.shards.iter() {
+            let mut shard = shard.write();
+            let (closed, open): (Vec<_>, Vec<_>) = shard
+                .drain()
+                .partition(|(actor_id, channels)| channels.is_closed() && prunable(actor_id));
+            shard.extend(open);
+            pruned.extend(closed.into_iter().map(|(actor_id, _)| actor_id));
+        }
+        pruned
+    }
+
+    /// Stops the running event loops of the actor, does nothing if none is running.
+    pub fn stop_actor(&self, actor_id: ActorId) {
+        if let Some(channel) = self.get_actor_channel::<ActorStop>(&actor_id) {
+            channel.sender().send(ActorStop).ok();
+        }
+    }
+
+    pub fn get_actor_channel<M: Message>(&self, actor_id: &ActorId) -> Option<MappedRwLockReadGuard<'_, M::Channel>> {
+        RwLockReadGuard::try_map(self.actor_channels.shard(actor_id).read(), |channels| {
+            channels.get(actor_id)?.get::<M>()
+        })
+        .ok()
+    }
+
+    pub fn extract_actor_channel<M: Message>(&self, actor_id: &ActorId) -> Option<M::Channel> {
+        self.actor_channels
+            .shard(actor_id)
+            .write()
+            .get_mut(actor_id)?
+            .remove::<M>()
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<ActorId: fmt::Display> System<ActorId> {
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let mut actors = 0;
+        let mut channels = self.channels.read().metrics(None);
+        for shard in self.actor_channels.shards.iter() {
+            let shard = shard.read();
+            actors += shard.len();
+            for (actor_id, actor_channels) in shard.iter() {
+                channels.extend(actor_channels.metrics(Some(actor_id.to_string())));
+            }
+        }
+        MetricsSnapshot {
+            actors,
+            channels,
+            restarts: self.runtime_counters.restarts(),
+            last_shutdown: self.runtime_counters.last_shutdown(),
+        }
+    }
+}
+
+impl<ActorId: Eq + Hash + fmt::Display> System<ActorId> {
+    pub fn actor_sender<M: Message>(&self, actor_id: ActorId) -> <M::Channel as Channel>::Sender {
+        infallible(self.actor_get_or_create::<M, _>(actor_id, |channel| channel.sender()))
+    }
+
+    /// Takes the receiver of the actor channel. If it was already taken, warns and returns a disconnected one.
+    pub fn actor_receiver<M: Message>(&self, actor_id: ActorId) -> <M::Channel as Channel>::Receiver {
+        let string_actor_id = actor_id.to_string();
+        let taken = infallible(self.actor_get_or_create::<M, _>(actor_id, take_receiver::<M>));
+        self.receiver_taken_again::<M>(Some(string_actor_id), taken)
     }
 
     pub fn actor_sender_of_custom_channel<M: Message>(
-        &mut self,
+        &self,
         actor_id: ActorId,
         constructor: impl FnOnce() -> M::Channel,
     ) -> <M::Channel as Channel>::Sender {
-        self.actor_channels
-            .entry(actor_id)
-            .or_default()
-            .sender::<M>(constructor)
+        let string_actor_id = actor_id.to_string();
+        infallible(self.actor_channels.get_or_create::<M, _>(
+            actor_id,
+            constructor,
+            |channel| channel.sender(),
+            || self.check_declared::<M>(Some(string_actor_id)),
+        ))
     }
 
     pub fn actor_receiver_of_custom_channel<M: Message>(
-        &mut self,
+        &self,
         actor_id: ActorId,
         constructor: impl FnOnce() -> M::Channel,
     ) -> <M::Channel as Channel>::Receiver {
-        self.actor_channels
-            .entry(actor_id)
-            .or_default()
-            .receiver::<M>(constructor)
+        let string_actor_id = actor_id.to_string();
+        let taken = infallible(self.actor_channels.get_or_create::<M, _>(
+            actor_id,
+            constructor,
+            take_receiver::<M>,
+            || self.check_declared::<M>(Some(string_actor_id.clone())),
+        ));
+        self.receiver_taken_again::<M>(Some(string_actor_id), taken)
     }
-}
 
-impl<ActorId: Eq + Hash + fmt::Display> System<ActorId> {
-    pub fn actor_sender<M: Message>(&mut self, actor_id: ActorId) -> <M::Channel as Channel>::Sender {
-        let string_actor_id = actor_id.to_string();
-        self.actor_sender_of_custom_channel::<M>(actor_id, || M::create_actor_channel(string_actor_id))
+    /// Takes a sender of the channel of a known actor, i.e. the one having any channel registered.
+    /// Unlike [`Self::actor_sender`], does not reopen the closed channel.
+    pub fn try_actor_sender<M: Message>(&self, actor_id: ActorId) -> Result<<M::Channel as Channel>::Sender, Error> {
+        let channel = ChannelRef::new::<M>(Some(actor_id.to_string()));
+        if !self.actor_channels.contains(&actor_id) {
+            return Err(Error::UnknownActor(channel));
+        }
+        if self
+            .get_actor_channel::<M>(&actor_id)
+            .is_some_and(|channel| channel.is_closed())
+        {
+            return Err(Error::ChannelClosed(channel));
+        }
+        self.actor_get_or_create::<M, _>(actor_id, |channel| channel.try_sender())?
+            .ok_or(Error::SenderTaken(channel))
     }
 
-    pub fn actor_receiver<M: Message>(&mut self, actor_id: ActorId) -> <M::Channel as Channel>::Receiver {
+    pub fn try_actor_receiver<M: Message>(
+        &self,
+        actor_id: ActorId,
+    ) -> Result<<M::Channel as Channel>::Receiver, Error> {
+        let channel = ChannelRef::new::<M>(Some(actor_id.to_string()));
+        self.actor_get_or_create::<M, _>(actor_id, |channel| channel.try_receiver())?
+            .ok_or(Error::ReceiverTaken(channel))
+    }
+
+    fn actor_get_or_create<M: Message, T>(
+        &self,
+        actor_id: ActorId,
+        take: impl Fn(&M::Channel) -> T,
+    ) -> Result<T, Error> {
         let string_actor_id = actor_id.to_string();
-        self.actor_receiver_of_custom_channel::<M>(actor_id, || M::create_actor_channel(string_actor_id))
+        self.actor_channels.get_or_create::<M, _>(
+            actor_id,
+            || M::create_actor_channel_with(string_actor_id.clone(), &self.config),
+            take,
+            || self.check_declared::<M>(Some(string_actor_id.clone())),
+        )
+    }
+
+    /// Reports the undeclared channels created in the frozen system, and the channels having senders
+    /// but no receiver taken, so nobody ever receives the messages sent to them.
+    pub fn validate(&self) -> ValidationReport {
+        let without_receiver = self
+            .filter_channels(|info| {
+                info.receiver_taken == Some(false) && info.senders.is_some_and(|senders| senders > 0)
+            })
+            .into_iter()
+            .map(|(channel, _)| channel)
+            .collect();
+        ValidationReport {
+            undeclared: self.undeclared.lock().clone(),
+            without_receiver,
+            receivers_taken_again: self.receivers_taken_again.lock().clone(),
+        }
+    }
+
+    /// Lists the channels with queued messages, but no receiver taken to ever receive them.
+    pub fn orphaned_channels(&self) -> Vec<OrphanedChannel> {
+        self.filter_channels(|info| info.receiver_taken == Some(false) && info.queue_len.is_some_and(|len| len > 0))
+            .into_iter()
+            .map(|(channel, info)| OrphanedChannel {
+                channel,
+                queue_len: info.queue_len.unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    fn filter_channels(&self, filter: impl Fn(&ChannelInfo) -> bool) -> Vec<(ChannelRef, ChannelInfo)> {
+        let mut found = filter_channels(&self.channels.read(), None, &filter);
+        for shard in self.actor_channels.shards.iter() {
+            for (actor_id, channels) in shard.read().iter() {
+                found.extend(filter_channels(channels, Some(actor_id.to_string()), &filter));
+            }
+        }
+        found
     }
 }
 
 impl<ActorId> System<ActorId> {
-    pub fn get_channel<M: Message>(&self) -> Option<&M::Channel> {
-        self.channels.get::<M>()
+    pub fn channels(&self) -> Vec<ChannelInfo> {
+        self.channels.read().infos()
     }
 
-    pub fn extract_channel<M: Message>(&mut self) -> Option<M::Channel> {
-        self.channels.remove::<M>()
+    pub fn get_channel<M: Message>(&self) -> Option<MappedRwLockReadGuard<'_, M::Channel>> {
+        RwLockReadGuard::try_map(self.channels.read(), |channels| channels.get::<M>()).ok()
+    }
+
+    pub fn extract_channel<M: Message>(&self) -> Option<M::Channel> {
+        self.channels.write().remove::<M>()
     }
 
     pub fn sender_of_custom_channel<M: Message>(
-        &mut self,
+        &self,
         constructor: impl FnOnce() -> M::Channel,
     ) -> <M::Channel as Channel>::Sender {
-        self.channels.sender::<M>(constructor)
+        infallible(self.get_or_create::<M, _>(
+            constructor,
+            |channel| channel.sender(),
+            || self.check_declared::<M>(None),
+        ))
     }
 
     pub fn receiver_of_custom_channel<M: Message>(
-        &mut self,
+        &self,
         constructor: impl FnOnce() -> M::Channel,
     ) -> <M::Channel as Channel>::Receiver {
-        self.channels.receiver::<M>(constructor)
+        let taken =
+            infallible(self.get_or_create::<M, _>(constructor, take_receiver::<M>, || self.check_declared::<M>(None)));
+        self.receiver_taken_again::<M>(None, taken)
     }
 
-    pub fn sender<M: Message>(&mut self) -> <M::Channel as Channel>::Sender {
-        self.sender_of_custom_channel::<M>(|| M::create_channel())
+    fn get_or_create<M: Message, T>(
+        &self,
+        constructor: impl FnOnce() -> M::Channel,
+        take: impl Fn(&M::Channel) -> T,
+        on_create: impl FnOnce() -> Result<(), Error>,
+    ) -> Result<T, Error> {
+        if let Some(value) = self.channels.read().resolve::<M, T>(&take) {
+            return Ok(value);
+        }
+
+        let mut channels = self.channels.write();
+        if channels.get::<M>().is_none() {
+            on_create()?;
+        }
+        Ok(channels.resolve_or_insert::<M, T>(constructor, &take))
     }
 
-    pub fn receiver<M: Message>(&mut self) -> <M::Channel as Channel>::Receiver {
-        self.receiver_of_custom_channel::<M>(|| M::create_channel())
+    fn get_or_create_configured<M: Message, T>(&self, take: impl Fn(&M::Channel) -> T) -> Result<T, Error> {
+        self.get_or_create::<M, _>(
+            || M::create_channel_with(&self.config),
+            take,
+            || self.check_declared::<M>(None),
+        )
     }
 
-    pub fn close_all_channels(&mut self) {
-        self.channels.clear();
+    pub fn sender<M: Message>(&self) -> <M::Channel as Channel>::Sender {
+        infallible(self.get_or_create_configured::<M, _>(|channel| channel.sender()))
     }
 
-    pub fn next_task_id(&mut self) -> TaskId {
-        let task_id = self.next_task_id;
-        self.next_task_id = TaskId(task_id.0 + 1);
-        task_id
+    /// Takes the receiver of the channel. If it was already taken, warns and returns a disconnected one.
+    pub fn receiver<M: Message>(&self) -> <M::Channel as Channel>::Receiver {
+        let taken = infallible(self.get_or_create_configured::<M, _>(take_receiver::<M>));
+        self.receiver_taken_again::<M>(None, taken)
+    }
+
+    /// Unlike [`Self::sender`], does not reopen the closed channel.
+    pub fn try_sender<M: Message>(&self) -> Result<<M::Channel as Channel>::Sender, Error> {
+        if self.get_channel::<M>().is_some_and(|channel| channel.is_closed()) {
+            return Err(Error::ChannelClosed(ChannelRef::new::<M>(None)));
+        }
+        self.get_or_create_configured::<M, _>(|channel| channel.try_sender())?
+            .ok_or_else(|| Error::SenderTaken(ChannelRef::new::<M>(None)))
+    }
+
+    pub fn try_receiver<M: Message>(&self) -> Result<<M::Channel as Channel>::Receiver, Error> {
+        self.get_or_create_configured::<M, _>(|channel| channel.try_receiver())?
+            .ok_or_else(|| Error::ReceiverTaken(ChannelRef::new::<M>(None)))
+    }
+
+    pub fn close_all_channels(&self) {
+        self.channels.write().clear();
+    }
+
+    pub fn next_task_id(&self) -> TaskId {
+        TaskId(self.next_task_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn set_task_hook(&self, hook: impl Fn(TaskId, TaskFailure<'_>) + Send + Sync + 'static) {
+        *self.task_hook.write() = Some(Arc::new(hook));
+    }
+
+    pub fn remove_task_hook(&self) -> Option<TaskHook> {
+        self.task_hook.write().take()
+    }
+
+    pub fn task_hook(&self) -> Option<TaskHook> {
+        self.task_hook.read().clone()
+    }
+
+    /// The hook slot itself, so a running task finds the hook installed after it was spawned.
+    pub(crate) fn task_hook_slot(&self) -> Arc<RwLock<Option<TaskHook>>> {
+        self.task_hook.clone()
+    }
+
+    pub fn runtime_counters(&self) -> &RuntimeCounters {
+        &self.runtime_counters
     }
 
     pub fn into_context(self) -> Context<ActorId> {
         self.into()
     }
 
-    pub fn recv_shutdown(&mut self) -> impl Future<Output = bool> {
+    pub fn recv_shutdown(&self) -> impl Future<Output = bool> {
         let mut receiver = self.receiver::<SystemShutdown>();
         async move {
             if receiver.changed().await.is_ok() {
@@ -199,7 +706,384 @@ impl<ActorId> System<ActorId> {
         }
     }
 
-    pub fn shutdown(&mut self) {
+    pub fn shutdown(&self) {
         self.sender::<SystemShutdown>().send_replace(Some(SystemShutdown));
+        for channel in self.shutdown_phases.read().values() {
+            channel.sender().send_replace(Some(SystemShutdown));
+        }
+    }
+
+    pub fn shutdown_phases(&self) -> Vec<ShutdownPhase> {
+        let mut phases = vec![ShutdownPhase::default()];
+        phases.extend(self.shutdown_phases.read().keys().copied());
+        phases
+    }
+
+    pub fn shutdown_receiver(&self, phase: ShutdownPhase) -> Receiver<SystemShutdown> {
+        if phase == ShutdownPhase::default() {
+            return self.receiver::<SystemShutdown>();
+        }
+
+        let receiver = self
+            .shutdown_phases
+            .read()
+            .get(&phase)
+            .map(|channel| channel.receiver());
+        receiver.unwrap_or_else(|| {
+            self.shutdown_phases
+                .write()
+                .entry(phase)
+                .or_insert_with(WatchChannel::create)
+                .receiver()
+        })
+    }
+
+    pub fn is_shutdown_channel_closed(&self, phase: ShutdownPhase) -> Option<bool> {
+        if phase == ShutdownPhase::default() {
+            self.get_channel::<SystemShutdown>().map(|channel| channel.is_closed())
+        } else {
+            self.shutdown_phases
+                .read()
+                .get(&phase)
+                .map(|channel| channel.is_closed())
+        }
+    }
+
+    pub fn shutdown_phase(&self, phase: ShutdownPhase) {
+        if phase == ShutdownPhase::default() {
+            self.sender::<SystemShutdown>()
+        } else {
+            self.shutdown_phases
+                .write()
+                .entry(phase)
+                .or_insert_with(WatchChannel::create)
+                .sender()
+        }
+        .send_replace(Some(SystemShutdown));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::{
+        BroadcastChannel, Channel, ChannelKind, ChannelRef, Context, Error, Freeze, Message, MpscChannel, Overflow,
+        OverflowChannel, PriorityChannel, System, SystemConfig, UnboundedMpscChannel,
+    };
+
+    struct Value(u32);
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+    }
+
+    struct Event;
+
+    impl Message for Event {
+        type Channel = UnboundedMpscChannel<Self>;
+    }
+
+    #[derive(Clone)]
+    struct Notice;
+
+    impl Message for Notice {
+        type Channel = BroadcastChannel<Self>;
+    }
+
+    #[tokio::test]
+    async fn concurrent_actor_channels() {
+        let system = Arc::new(System::<u32>::with_actor_shards(4));
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let system = system.clone();
+                thread::spawn(move || {
+                    for id in 0..100 {
+                        system.actor_sender::<Value>(id).try_send(Value(id)).ok().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        for id in 0..100 {
+            let mut receiver = system.actor_receiver::<Value>(id);
+            for _ in 0..8 {
+                assert_eq!(receiver.recv().await.unwrap().0, id);
+            }
+            assert!(receiver.try_recv().is_err());
+        }
+        assert_eq!(system.next_task_id().0, 1);
+    }
+
+    #[tokio::test]
+    async fn introspect_channels() {
+        let system = System::<u32>::default();
+        assert!(system.actor_ids().is_empty());
+
+        let value_out = system.actor_sender::<Value>(1);
+        value_out.send(Value(1)).await.ok().unwrap();
+        value_out.send(Value(2)).await.ok().unwrap();
+        system.actor_sender::<Event>(1).send(Event).ok().unwrap();
+        drop(system.actor_receiver::<Value>(2));
+        let _notice_in = system.receiver::<Notice>();
+
+        let mut actor_ids = system.actor_ids();
+        actor_ids.sort();
+        assert_eq!(actor_ids, [1, 2]);
+
+        let infos = system.channels_of(&1);
+        assert_eq!(infos.len(), 2);
+        let value = infos.iter().find(|info| info.message.ends_with("Value")).unwrap();
+        assert_eq!(value.kind, ChannelKind::Mpsc);
+        assert_eq!(value.capacity, Some(1024));
+        assert_eq!(value.queue_len, Some(2));
+        assert!(!value.is_closed);
+        let event = infos.iter().find(|info| info.message.ends_with("Event")).unwrap();
+        assert_eq!(event.kind, ChannelKind::UnboundedMpsc);
+        assert_eq!(event.capacity, None);
+        assert_eq!(event.queue_len, Some(1));
+
+        assert!(system.channels_of(&2)[0].is_closed);
+        assert!(system.channels_of(&3).is_empty());
+
+        let infos = system.channels();
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].kind, ChannelKind::Broadcast);
+        assert_eq!(infos[0].queue_len, Some(0));
+    }
+
+    #[tokio::test]
+    async fn configured_capacities() {
+        struct Other;
+
+        impl Message for Other {
+            type Channel = MpscChannel<Self>;
+        }
+
+        struct Own;
+
+        impl Message for Own {
+            type Channel = MpscChannel<Self>;
+
+            fn create_actor_channel(_actor_id: impl Into<String>) -> Self::Channel {
+                MpscChannel::new(2)
+            }
+        }
+
+        struct Tick(u32);
+
+        impl Message for Tick {
+            type Channel = OverflowChannel<Self>;
+
+            fn create_channel() -> Self::Channel {
+                OverflowChannel::new(1, Overflow::DropOldest)
+            }
+        }
+
+        #[derive(Debug, PartialEq)]
+        struct Job(u32);
+
+        impl Message for Job {
+            type Channel = PriorityChannel<Self>;
+
+            fn create_channel() -> Self::Channel {
+                PriorityChannel::with_priority(1, |job: &Job| job.0)
+            }
+        }
+
+        let config = SystemConfig::new()
+            .default_capacity(ChannelKind::Mpsc, 16)
+            .default_capacity(ChannelKind::Broadcast, 8)
+            .message_capacity::<Other>(4)
+            .message_capacity::<Tick>(2)
+            .message_capacity::<Job>(3);
+        let ctx = Context::<i32>::from_system(System::with_config(config));
+
+        let _value_in = ctx.actor_receiver::<Value>(1);
+        let _other_in = ctx.actor_receiver::<Other>(1);
+        let _own_in = ctx.actor_receiver::<Own>(1);
+        let _event_in = ctx.actor_receiver::<Event>(1);
+        let _notice_in = ctx.receiver::<Notice>();
+
+        let capacities: Vec<_> = ctx
+            .system()
+            .channels_of(&1)
+            .into_iter()
+            .map(|info| (info.message.rsplit("::").next().unwrap(), info.capacity))
+            .collect();
+        assert_eq!(
+            capacities,
+            [
+                ("Event", None),
+                ("Value", Some(16)),
+                ("Other", Some(4)),
+                ("Own", Some(2))
+            ]
+        );
+        assert_eq!(ctx.system().channels()[0].capacity, Some(8));
+
+        let mut tick_in = ctx.receiver::<Tick>();
+        for value in 1..=3 {
+            ctx.sender::<Tick>().send(Tick(value)).await.ok().unwrap();
+        }
+        assert_eq!(ctx.system().get_channel::<Tick>().unwrap().dropped(), 1);
+        assert_eq!(tick_in.recv().await.unwrap().0, 2);
+
+        let mut job_in = ctx.receiver::<Job>();
+        for value in [1, 3, 2] {
+            ctx.sender::<Job>().send(Job(value)).await.unwrap();
+        }
+        assert_eq!(job_in.recv().await, Some(Job(3)));
+        assert_eq!(job_in.recv().await, Some(Job(2)));
+    }
+
+    #[test]
+    fn capacities_restored_after_panic() {
+        struct Faulty;
+
+        impl Message for Faulty {
+            type Channel = MpscChannel<Self>;
+
+            fn create_channel() -> Self::Channel {
+                panic!("faulty channel");
+            }
+        }
+
+        let system = System::<u32>::builder()
+            .config(SystemConfig::new().default_capacity(ChannelKind::Mpsc, 16))
+            .build();
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| system.sender::<Faulty>())).is_err());
+        assert_eq!(MpscChannel::<Value>::create().capacity(), Some(1024));
+    }
+
+    #[test]
+    #[should_panic(expected = "channel capacity must be positive")]
+    fn zero_capacity() {
+        SystemConfig::new().default_capacity(ChannelKind::Mpsc, 0);
+    }
+
+    #[test]
+    fn frozen_system() {
+        let system = System::<u32>::builder()
+            .actor_channel::<Value>(1)
+            .channel::<Notice>()
+            .freeze(Freeze::Report)
+            .build();
+        assert_eq!(system.channels_of(&1).len(), 2);
+        assert!(system.validate().is_ok());
+
+        let value_out = system.actor_sender::<Value>(1);
+        let _notice_out = system.sender::<Notice>();
+        let _notice_in = system.receiver::<Notice>();
+        system.stop_actor(2);
+        assert!(system.channels_of(&2).is_empty());
+        drop(system.actor_sender::<Event>(1));
+        let _event_in = system.receiver::<Event>();
+
+        let report = system.validate();
+        let event = ChannelRef {
+            message: std::any::type_name::<Event>(),
+            actor_id: Some("1".into()),
+        };
+        assert_eq!(
+            report.undeclared,
+            [
+                event.clone(),
+                ChannelRef {
+                    actor_id: None,
+                    ..event
+                }
+            ]
+        );
+        let value = ChannelRef {
+            message: std::any::type_name::<Value>(),
+            actor_id: Some("1".into()),
+        };
+        assert_eq!(report.without_receiver, [value]);
+
+        let _value_in = system.actor_receiver::<Value>(1);
+        assert!(system.validate().without_receiver.is_empty());
+        drop(value_out);
+    }
+
+    #[test]
+    #[should_panic(expected = "undeclared channel")]
+    fn frozen_system_panics() {
+        let system = System::<u32>::builder().freeze(Freeze::Panic).build();
+        system.shutdown();
+        system.actor_sender::<Value>(1);
+    }
+
+    #[test]
+    fn frozen_system_rejects_without_actor() {
+        let system = System::<u32>::builder()
+            .actor_channel::<Value>(1)
+            .freeze(Freeze::Panic)
+            .build();
+        assert_eq!(
+            system.try_actor_receiver::<Event>(2).err(),
+            Some(Error::Undeclared(ChannelRef::new::<Event>(Some("2".into()))))
+        );
+        assert_eq!(system.actor_ids(), [1]);
+        assert!(system.channels_of(&2).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "undeclared channel")]
+    fn frozen_system_panics_on_custom_channels() {
+        let system = System::<u32>::builder().freeze(Freeze::Panic).build();
+        system.actor_sender_of_custom_channel::<Value>(1, Value::create_channel);
+    }
+
+    #[tokio::test]
+    async fn orphaned_channels() {
+        let system = System::<u32>::default();
+        let value_out = system.actor_sender::<Value>(1);
+        value_out.send(Value(1)).await.ok().unwrap();
+        value_out.send(Value(2)).await.ok().unwrap();
+        system.sender::<Event>().send(Event).ok().unwrap();
+        drop(system.sender::<Event>());
+
+        let orphaned: Vec<_> = system
+            .orphaned_channels()
+            .into_iter()
+            .map(|orphaned| (orphaned.channel.actor_id, orphaned.queue_len))
+            .collect();
+        assert_eq!(orphaned, [(None, 1), (Some("1".into()), 2)]);
+
+        let mut value_in = system.actor_receiver::<Value>(1);
+        let _event_in = system.receiver::<Event>();
+        assert!(system.orphaned_channels().is_empty());
+
+        let mut again_in = system.actor_receiver::<Value>(1);
+        assert!(again_in.recv().await.is_none());
+        assert_eq!(value_in.recv().await.unwrap().0, 1);
+        let report = system.validate();
+        assert_eq!(report.receivers_taken_again.len(), 1);
+        assert_eq!(report.receivers_taken_again[0].actor_id.as_deref(), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn custom_receiver_taken_again() {
+        let system = System::<u32>::default();
+        let _value_in = system.actor_receiver_of_custom_channel::<Value>(1, Value::create_channel);
+        let mut again_in = system.actor_receiver_of_custom_channel::<Value>(1, Value::create_channel);
+        let _event_in = system.receiver_of_custom_channel::<Event>(Event::create_channel);
+        let _again_in = system.receiver_of_custom_channel::<Event>(Event::create_channel);
+
+        assert!(again_in.recv().await.is_none());
+        let taken_again: Vec<_> = system
+            .validate()
+            .receivers_taken_again
+            .into_iter()
+            .map(|channel| channel.actor_id)
+            .collect();
+        assert_eq!(taken_again, [Some("1".into()), None]);
     }
 }
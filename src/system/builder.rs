@@ -0,0 +1,150 @@
+use std::hash::Hash;
+use std::{any, fmt};
+
+use crate::system::{ActorStop, SystemShutdown};
+use crate::{Message, System, SystemConfig};
+
+/// What a frozen [`System`] does when an undeclared channel is about to be created lazily.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Freeze {
+    Panic,
+    /// Create the channel anyway and report it by [`System::validate`].
+    Report,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRef {
+    pub message: &'static str,
+    pub actor_id: Option<String>,
+}
+
+impl ChannelRef {
+    pub(crate) fn new<M: Message>(actor_id: Option<String>) -> Self {
+        Self {
+            message: any::type_name::<M>(),
+            actor_id,
+        }
+    }
+}
+
+impl fmt::Display for ChannelRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actor_id {
+            Some(actor_id) => write!(f, "`{}` of actor `{actor_id}`", self.message),
+            None => write!(f, "`{}`", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The channels created lazily in the frozen system.
+    pub undeclared: Vec<ChannelRef>,
+    /// The channels having live senders, but no receiver taken.
+    pub without_receiver: Vec<ChannelRef>,
+    /// The channels asked for the receiver after it was already taken.
+    pub receivers_taken_again: Vec<ChannelRef>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.undeclared.is_empty() && self.without_receiver.is_empty() && self.receivers_taken_again.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedChannel {
+    pub channel: ChannelRef,
+    pub queue_len: usize,
+}
+
+type Declaration<ActorId> = Box<dyn FnOnce(&System<ActorId>)>;
+
+/// Declares the expected channels and actors up front. The declared channels are created by [`Self::build`].
+pub struct SystemBuilder<ActorId> {
+    config: SystemConfig,
+    declarations: Vec<Declaration<ActorId>>,
+    freeze: Option<Freeze>,
+}
+
+impl<ActorId> SystemBuilder<ActorId> {
+    pub fn new() -> Self {
+        Self {
+            config: Default::default(),
+            declarations: Vec::new(),
+            freeze: None,
+        }
+    }
+
+    pub fn config(mut self, config: SystemConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Freezes the built system, see [`System::freeze`].
+    pub fn freeze(mut self, freeze: Freeze) -> Self {
+        self.freeze = Some(freeze);
+        self
+    }
+
+    pub fn build(self) -> System<ActorId> {
+        let system = System::with_config(self.config);
+        declare_channel::<SystemShutdown, _>(&system);
+        for declare in self.declarations {
+            declare(&system);
+        }
+        if let Some(freeze) = self.freeze {
+            system.freeze(freeze);
+        }
+        system
+    }
+}
+
+impl<ActorId> Default for SystemBuilder<ActorId> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ActorId: 'static> SystemBuilder<ActorId> {
+    pub fn channel<M: Message>(mut self) -> Self {
+        self.declarations.push(Box::new(declare_channel::<M, _>));
+        self
+    }
+}
+
+impl<ActorId: Eq + Hash + fmt::Display + 'static> SystemBuilder<ActorId> {
+    pub fn actor(mut self, actor_id: ActorId) -> Self {
+        self.declarations.push(Box::new(move |system| {
+            declare_actor_channel::<ActorStop, _>(system, actor_id)
+        }));
+        self
+    }
+
+    /// Declares the actor along with the channel of its messages.
+    pub fn actor_channel<M: Message>(mut self, actor_id: ActorId) -> Self
+    where
+        ActorId: Clone,
+    {
+        self = self.actor(actor_id.clone());
+        self.declarations
+            .push(Box::new(move |system| declare_actor_channel::<M, _>(system, actor_id)));
+        self
+    }
+}
+
+fn declare_channel<M: Message, ActorId>(system: &System<ActorId>) {
+    let declared = system.get_or_create::<M, _>(|| M::create_channel_with(&system.config), |_| (), || Ok(()));
+    declared.expect("channels are declared before freezing");
+}
+
+fn declare_actor_channel<M: Message, ActorId: Eq + Hash + fmt::Display>(system: &System<ActorId>, actor_id: ActorId) {
+    let string_actor_id = actor_id.to_string();
+    let declared = system.actor_channels.get_or_create::<M, _>(
+        actor_id,
+        || M::create_actor_channel_with(string_actor_id, &system.config),
+        |_| (),
+        || Ok(()),
+    );
+    declared.expect("channels are declared before freezing");
+}
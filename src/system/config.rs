@@ -0,0 +1,77 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::{ChannelKind, Message};
+
+thread_local! {
+    static KIND_CAPACITIES: RefCell<HashMap<ChannelKind, usize>> = RefCell::default();
+}
+
+/// The capacity of the channels of the given kind configured for the system creating the channel, if any.
+pub(crate) fn default_capacity(kind: ChannelKind, fallback: usize) -> usize {
+    KIND_CAPACITIES.with(|capacities| capacities.borrow().get(&kind).copied().unwrap_or(fallback))
+}
+
+/// Settings consulted by the [`System`](crate::System) when it lazily creates channels.
+#[derive(Debug, Clone, Default)]
+pub struct SystemConfig {
+    kind_capacities: HashMap<ChannelKind, usize>,
+    message_capacities: HashMap<TypeId, usize>,
+    actor_shards: Option<usize>,
+}
+
+impl SystemConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the capacity of the channels of the given kind created by [`Channel::create`](crate::Channel::create).
+    /// The messages creating the channels by themselves keep their capacities.
+    pub fn default_capacity(mut self, kind: ChannelKind, capacity: usize) -> Self {
+        assert!(capacity > 0, "channel capacity must be positive");
+        self.kind_capacities.insert(kind, capacity);
+        self
+    }
+
+    /// Sets the capacity of the message channel, whichever way it is created.
+    pub fn message_capacity<M: Message>(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "channel capacity must be positive");
+        self.message_capacities.insert(TypeId::of::<M>(), capacity);
+        self
+    }
+
+    pub fn actor_shards(mut self, shards: usize) -> Self {
+        self.actor_shards = Some(shards);
+        self
+    }
+
+    pub fn kind_capacity(&self, kind: ChannelKind) -> Option<usize> {
+        self.kind_capacities.get(&kind).copied()
+    }
+
+    pub fn message_capacity_of<M: Message>(&self) -> Option<usize> {
+        self.message_capacities.get(&TypeId::of::<M>()).copied()
+    }
+
+    pub fn get_actor_shards(&self) -> Option<usize> {
+        self.actor_shards
+    }
+
+    /// Runs the channel constructor with the default capacities of this config.
+    pub(crate) fn scope<T>(&self, create: impl FnOnce() -> T) -> T {
+        let _restore =
+            RestoreCapacities(KIND_CAPACITIES.with(|capacities| capacities.replace(self.kind_capacities.clone())));
+        create()
+    }
+}
+
+/// Puts the outer capacities back, even if the channel constructor panics.
+struct RestoreCapacities(HashMap<ChannelKind, usize>);
+
+impl Drop for RestoreCapacities {
+    fn drop(&mut self) {
+        let outer = std::mem::take(&mut self.0);
+        KIND_CAPACITIES.with(|capacities| *capacities.borrow_mut() = outer);
+    }
+}
@@ -0,0 +1,442 @@
+use std::any::Any;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use futures::future;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use tokio::task::{JoinError, JoinHandle};
+use tokio::time::{self, Instant};
+
+use crate::system::ShutdownPhase;
+use crate::TaskId;
+
+pub type TaskError = Box<dyn Error + Send + Sync>;
+pub type TaskResult = Result<(), TaskError>;
+pub type TaskHook = Arc<dyn Fn(TaskId, TaskFailure<'_>) + Send + Sync>;
+
+#[derive(Debug, Copy, Clone)]
+pub enum TaskFailure<'a> {
+    Error(&'a (dyn Error + Send + Sync)),
+    Panic(&'a (dyn Any + Send)),
+}
+
+impl TaskFailure<'_> {
+    pub fn message(&self) -> String {
+        match self {
+            Self::Error(err) => err.to_string(),
+            Self::Panic(payload) => panic_message(*payload),
+        }
+    }
+}
+
+pub enum TaskOutcome {
+    Finished,
+    Failed(TaskError),
+    Panicked(Box<dyn Any + Send>),
+    Cancelled,
+}
+
+impl TaskOutcome {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Finished)
+    }
+}
+
+impl fmt::Debug for TaskOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Finished => write!(f, "Finished"),
+            Self::Failed(err) => f.debug_tuple("Failed").field(err).finish(),
+            Self::Panicked(payload) => f.debug_tuple("Panicked").field(&panic_message(&**payload)).finish(),
+            Self::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl From<Result<TaskResult, JoinError>> for TaskOutcome {
+    fn from(result: Result<TaskResult, JoinError>) -> Self {
+        match result {
+            Ok(Ok(())) => Self::Finished,
+            Ok(Err(err)) => Self::Failed(err),
+            Err(err) => match err.try_into_panic() {
+                Ok(payload) => Self::Panicked(payload),
+                Err(_) => Self::Cancelled,
+            },
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".into()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskMeta {
+    pub name: Option<String>,
+    pub actor_id: Option<String>,
+    pub phase: ShutdownPhase,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    Finished,
+    /// Returned an error or panicked.
+    Failed,
+    Aborted,
+}
+
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::SeqCst) {
+            self.inner.notify.notify_waiters();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CurrentTask {
+    id: TaskId,
+    token: CancellationToken,
+}
+
+tokio::task_local! {
+    static CURRENT_TASK: CurrentTask;
+}
+
+pub(crate) fn scope<T: Future>(id: TaskId, token: CancellationToken, future: T) -> impl Future<Output = T::Output> {
+    CURRENT_TASK.scope(CurrentTask { id, token }, future)
+}
+
+pub fn current_task_id() -> Option<TaskId> {
+    CURRENT_TASK.try_with(|task| task.id).ok()
+}
+
+pub fn current_cancellation_token() -> CancellationToken {
+    CURRENT_TASK.try_with(|task| task.token.clone()).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub name: Option<String>,
+    pub actor_id: Option<String>,
+    pub phase: ShutdownPhase,
+    pub spawned_at: SystemTime,
+    pub state: TaskState,
+}
+
+struct TaskEntry {
+    meta: TaskMeta,
+    spawned_at: SystemTime,
+    token: CancellationToken,
+    state: TaskState,
+    handle: JoinHandle<TaskResult>,
+}
+
+impl TaskEntry {
+    fn info(&self, id: TaskId) -> TaskInfo {
+        TaskInfo {
+            id,
+            name: self.meta.name.clone(),
+            actor_id: self.meta.actor_id.clone(),
+            phase: self.meta.phase,
+            spawned_at: self.spawned_at,
+            state: self.state,
+        }
+    }
+}
+
+/// The completed tasks are kept until joined, but no more than this number of the latest ones.
+pub const COMPLETED_TASKS_CAPACITY: usize = 1024;
+
+#[derive(Default)]
+struct Tasks {
+    running: HashMap<TaskId, TaskEntry>,
+    completed: VecDeque<(TaskId, TaskEntry)>,
+}
+
+impl Tasks {
+    fn entries(&self) -> impl Iterator<Item = (&TaskId, &TaskEntry)> {
+        self.running
+            .iter()
+            .chain(self.completed.iter().map(|(id, entry)| (id, entry)))
+    }
+
+    fn remove(&mut self, id: &TaskId) -> Option<TaskEntry> {
+        self.running.remove(id).or_else(|| {
+            let index = self.completed.iter().position(|(completed_id, _)| completed_id == id)?;
+            self.completed.remove(index).map(|(_, entry)| entry)
+        })
+    }
+
+    fn insert(&mut self, id: TaskId, meta: TaskMeta, token: CancellationToken, handle: JoinHandle<TaskResult>) {
+        self.running.insert(
+            id,
+            TaskEntry {
+                meta,
+                spawned_at: SystemTime::now(),
+                token,
+                state: TaskState::Running,
+                handle,
+            },
+        );
+    }
+
+    fn complete(&mut self, id: TaskId, state: TaskState) {
+        if let Some(mut entry) = self.running.remove(&id) {
+            entry.state = state;
+            self.completed.push_back((id, entry));
+            if self.completed.len() > COMPLETED_TASKS_CAPACITY {
+                self.completed.pop_front();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TaskHandles {
+    tasks: Arc<Mutex<Tasks>>,
+}
+
+impl TaskHandles {
+    pub fn add(&self, id: TaskId, handle: JoinHandle<TaskResult>) {
+        self.add_with_meta(id, TaskMeta::default(), Default::default(), handle);
+    }
+
+    pub fn add_with_meta(&self, id: TaskId, meta: TaskMeta, token: CancellationToken, handle: JoinHandle<TaskResult>) {
+        self.tasks.lock().insert(id, meta, token, handle);
+    }
+
+    /// Spawns the task holding the lock, so it cannot complete before being tracked.
+    pub(crate) fn spawn<T>(&self, id: TaskId, meta: TaskMeta, token: CancellationToken, future: T)
+    where
+        T: Future<Output = TaskResult> + Send + 'static,
+    {
+        let mut tasks = self.tasks.lock();
+        let handle = tokio::spawn(future);
+        tasks.insert(id, meta, token, handle);
+    }
+
+    /// Moves the task to the bounded record of the completed ones, where it waits to be joined.
+    pub(crate) fn complete(&self, id: TaskId, state: TaskState) {
+        self.tasks.lock().complete(id, state);
+    }
+
+    pub fn cancel(&self, id: &TaskId) -> bool {
+        if let Some(entry) = self.tasks.lock().running.get(id) {
+            entry.token.cancel();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn abort(&self, id: &TaskId) -> bool {
+        let mut tasks = self.tasks.lock();
+        if let Some(entry) = tasks.running.get(id) {
+            entry.handle.abort();
+            tasks.complete(*id, TaskState::Aborted);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get(&self, id: &TaskId) -> Option<TaskInfo> {
+        self.tasks
+            .lock()
+            .entries()
+            .find(|(task_id, _)| *task_id == id)
+            .map(|(id, entry)| entry.info(*id))
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let mut tasks: Vec<_> = self.tasks.lock().entries().map(|(id, entry)| entry.info(*id)).collect();
+        tasks.sort_by_key(|task| task.id);
+        tasks
+    }
+
+    pub fn remove(&self, id: &TaskId) -> Option<JoinHandle<TaskResult>> {
+        self.tasks.lock().remove(id).map(|entry| entry.handle)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        let tasks = self.tasks.lock();
+        tasks.running.len() + tasks.completed.len()
+    }
+
+    pub fn phases(&self) -> BTreeSet<ShutdownPhase> {
+        self.tasks
+            .lock()
+            .running
+            .values()
+            .map(|entry| entry.meta.phase)
+            .collect()
+    }
+
+    pub async fn join(&self, id: &TaskId) -> Result<(), JoinError> {
+        if let Some(handle) = self.remove(id) {
+            handle.await.map(|_| ())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub async fn join_outcome(&self, id: &TaskId) -> Option<TaskOutcome> {
+        Some(self.remove(id)?.await.into())
+    }
+
+    pub async fn join_all_outcomes(&self) -> Vec<(TaskId, TaskOutcome)> {
+        let mut outcomes = future::join_all(
+            self.drain(None)
+                .into_iter()
+                .map(|(id, handle)| async move { (id, handle.await.into()) }),
+        )
+        .await;
+        outcomes.sort_by_key(|(id, _)| *id);
+        outcomes
+    }
+
+    pub async fn join_all(&self) {
+        let handles = self.drain(None).into_iter().map(|(_, handle)| handle);
+        future::join_all(handles).await;
+    }
+
+    pub async fn join_phase(&self, phase: ShutdownPhase) {
+        let handles = self.drain(Some(phase)).into_iter().map(|(_, handle)| handle);
+        future::join_all(handles).await;
+    }
+
+    pub async fn join_all_timeout(&self, timeout: Duration) -> ShutdownReport {
+        self.join_all_until(Instant::now() + timeout).await
+    }
+
+    pub async fn join_phase_timeout(&self, phase: ShutdownPhase, timeout: Duration) -> ShutdownReport {
+        self.join_phase_until(phase, Instant::now() + timeout).await
+    }
+
+    pub async fn join_all_until(&self, deadline: Instant) -> ShutdownReport {
+        Self::join_until(self.drain(None), deadline).await
+    }
+
+    pub async fn join_phase_until(&self, phase: ShutdownPhase, deadline: Instant) -> ShutdownReport {
+        Self::join_until(self.drain(Some(phase)), deadline).await
+    }
+
+    fn drain(&self, phase: Option<ShutdownPhase>) -> Vec<(TaskId, JoinHandle<TaskResult>)> {
+        let mut tasks = self.tasks.lock();
+        let ids: Vec<_> = tasks
+            .entries()
+            .filter(|(_, entry)| phase.map_or(true, |phase| entry.meta.phase == phase))
+            .map(|(id, _)| *id)
+            .collect();
+
+        ids.into_iter()
+            .filter_map(|id| tasks.remove(&id).map(|entry| (id, entry.handle)))
+            .collect()
+    }
+
+    async fn join_until(handles: Vec<(TaskId, JoinHandle<TaskResult>)>, deadline: Instant) -> ShutdownReport {
+        let results = future::join_all(handles.into_iter().map(|(id, mut handle)| async move {
+            let result = match time::timeout_at(deadline, &mut handle).await {
+                Ok(result) => result,
+                Err(_) => {
+                    handle.abort();
+                    handle.await
+                },
+            };
+            (id, result)
+        }))
+        .await;
+
+        let mut report = ShutdownReport::default();
+        for (id, result) in results {
+            match result {
+                Ok(Ok(())) => report.finished.push(id),
+                Ok(Err(_)) => report.failed.push(id),
+                Err(err) if err.is_panic() => report.panicked.push(id),
+                Err(_) => report.aborted.push(id),
+            }
+        }
+        report.sort();
+        report
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub finished: Vec<TaskId>,
+    pub failed: Vec<TaskId>,
+    pub aborted: Vec<TaskId>,
+    pub panicked: Vec<TaskId>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty() && self.aborted.is_empty() && self.panicked.is_empty()
+    }
+
+    pub fn merge(&mut self, other: ShutdownReport) {
+        self.finished.extend(other.finished);
+        self.failed.extend(other.failed);
+        self.aborted.extend(other.aborted);
+        self.panicked.extend(other.panicked);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.finished.sort();
+        self.failed.sort();
+        self.aborted.sort();
+        self.panicked.sort();
+    }
+}
@@ -0,0 +1,151 @@
+//! Instrumentation of tasks and channels, compiled to nothing unless the `tracing` feature is enabled.
+//! The warnings about misuse are printed to stderr without it, as they would go unnoticed otherwise.
+//! Sends and receives are traced by the senders and receivers of truba's own channels. The broadcast and watch
+//! channels hand out the tokio halves as is, so their messages are not traced.
+
+use std::future::Future;
+
+use crate::task::{TaskFailure, TaskMeta};
+use crate::{ChannelRef, TaskId};
+
+#[cfg(feature = "tracing")]
+pub(crate) fn instrument<F: Future>(task_id: TaskId, meta: &TaskMeta, future: F) -> impl Future<Output = F::Output> {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "task",
+        task_id = task_id.0,
+        actor_id = meta.actor_id.as_deref(),
+        name = meta.name.as_deref(),
+        phase = meta.phase.0,
+    );
+    future.instrument(span)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn instrument<F: Future>(_task_id: TaskId, _meta: &TaskMeta, future: F) -> F {
+    future
+}
+
+pub(crate) fn task_failed(failure: &TaskFailure<'_>) {
+    #[cfg(feature = "tracing")]
+    match failure {
+        TaskFailure::Error(_) => tracing::warn!(error = %failure.message(), "task failed"),
+        TaskFailure::Panic(_) => tracing::error!(panic = %failure.message(), "task panicked"),
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = failure;
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn sent<T, R, E>(result: &Result<R, E>) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        message_type = std::any::type_name::<T>(),
+        delivered = result.is_ok(),
+        "message sent"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = result;
+}
+
+pub(crate) fn received<T>() {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(message_type = std::any::type_name::<T>(), "message received");
+}
+
+pub(crate) fn receiver_taken_again(channel: &ChannelRef) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(%channel, "receiver is already taken, a disconnected one is handed out");
+    #[cfg(not(feature = "tracing"))]
+    eprintln!("truba: receiver of channel {channel} is already taken, a disconnected one is handed out");
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use parking_lot::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::{Context, Message, MpscChannel};
+
+    #[derive(Default)]
+    struct Recorder {
+        next_id: AtomicU64,
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Line<'a>(&'a mut String);
+
+    impl Visit for Line<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut line = span.metadata().name().to_string();
+            span.record(&mut Line(&mut line));
+            self.lines.lock().push(line);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = String::from("event");
+            event.record(&mut Line(&mut line));
+            self.lines.lock().push(line);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    struct Value;
+
+    impl Message for Value {
+        type Channel = MpscChannel<Self>;
+    }
+
+    #[tokio::test]
+    async fn instrument_tasks_and_sends() {
+        let recorder = Recorder::default();
+        let lines = recorder.lines.clone();
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        let ctx = Context::<i32>::new();
+        let mut value_in = ctx.actor_receiver::<Value>(1);
+        let sender_ctx = ctx.clone();
+        let task_id = ctx.task().name("producer").actor(&7).spawn(async move {
+            sender_ctx.actor_sender::<Value>(1).send(Value).await.ok();
+        });
+        value_in.recv().await.unwrap();
+        ctx.join(&task_id).await.unwrap();
+
+        let lines = lines.lock();
+        assert_eq!(
+            lines[0],
+            format!("task task_id={} actor_id=\"7\" name=\"producer\" phase=0", task_id.0)
+        );
+        assert!(lines[1].starts_with("event message=message sent message_type=\"truba::trace::tests::Value\""));
+        assert!(lines[1].ends_with("delivered=true"));
+        assert_eq!(
+            lines[2],
+            "event message=message received message_type=\"truba::trace::tests::Value\""
+        );
+    }
+}